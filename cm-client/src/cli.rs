@@ -1,4 +1,10 @@
-use crackmes::{Language, Platform};
+use crate::{extract::Existing, output::Format};
+use crackmes::{
+    error::CrackmeResult,
+    search::{SearchQuery, MAX_RATING, MIN_RATING},
+    Language, Platform,
+};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(StructOpt, PartialEq, Debug)]
@@ -6,29 +12,116 @@ use structopt::StructOpt;
 pub struct App {
     #[structopt(subcommand)]
     pub nested: Command,
+
+    #[structopt(
+        help = "Only use pages cached by earlier runs, without going online",
+        long,
+        global = true
+    )]
+    pub offline: bool,
 }
 
 #[derive(StructOpt, PartialEq, Debug)]
 pub enum Command {
     #[structopt(name = "get", about = "Used to get crackmes and extract them")]
     Get {
+        #[structopt(
+            help = "The IDs of the crackmes (read from stdin if there are none and no file)",
+            parse(try_from_str = id_parser)
+        )]
+        ids: Vec<String>,
+
+        #[structopt(
+            help = "Read the IDs from this file, one per line (- for stdin)",
+            long,
+            parse(from_os_str)
+        )]
+        from_file: Option<PathBuf>,
+
+        #[structopt(
+            help = "The number of crackmes to get at once",
+            long,
+            default_value = "4"
+        )]
+        jobs: usize,
+
+        #[structopt(flatten)]
+        extract: ExtractArgs,
+    },
+
+    #[structopt(
+        name = "clear-cache",
+        about = "Deletes the pages cached by earlier runs (the cache has no size limit)"
+    )]
+    ClearCache,
+
+    #[structopt(
+        name = "info",
+        about = "Used to show a crackme's details without downloading it"
+    )]
+    Info {
         #[structopt(help = "The ID of the crackme", parse(try_from_str = id_parser))]
         id: String,
+
+        #[structopt(help = "Print the crackme as JSON", long)]
+        json: bool,
     },
 
     #[structopt(
         name = "search",
         about = "Used to search for crackmes based on some criteria (or default search if no criteria chosen)"
     )]
-    Search(SearchArgs),
+    Search {
+        #[structopt(flatten)]
+        args: SearchArgs,
+
+        #[structopt(flatten)]
+        pages: PageArgs,
+
+        #[structopt(
+            help = "Print the results in this format instead of picking one",
+            long,
+            possible_values = Format::VARIANTS,
+            case_insensitive = true
+        )]
+        format: Option<Format>,
+
+        #[structopt(flatten)]
+        extract: ExtractArgs,
+    },
 
     #[structopt(name = "latest", about = "The crackmes from the latest page")]
     Latest {
         #[structopt(help = "The page number", default_value = "1")]
         page: u64,
+
+        #[structopt(flatten)]
+        pages: PageArgs,
+
+        #[structopt(
+            help = "Print the results in this format instead of picking one",
+            long,
+            possible_values = Format::VARIANTS,
+            case_insensitive = true
+        )]
+        format: Option<Format>,
+
+        #[structopt(flatten)]
+        extract: ExtractArgs,
     },
 }
 
+impl Command {
+    pub fn extract_args_mut(&mut self) -> Option<&mut ExtractArgs> {
+        match self {
+            Command::Get { extract, .. }
+            | Command::Search { extract, .. }
+            | Command::Latest { extract, .. } => Some(extract),
+            Command::Info { .. } | Command::ClearCache => None,
+        }
+    }
+}
+
 #[derive(StructOpt, PartialEq, Debug)]
 pub struct SearchArgs {
     #[structopt(
@@ -62,6 +155,101 @@ pub struct SearchArgs {
     pub platform: Option<Platform>,
 }
 
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct ExtractArgs {
+    #[structopt(help = "Don't make any of the extracted files executable", long)]
+    pub no_exec: bool,
+
+    #[structopt(
+        help = "The directory to extract crackmes into",
+        long,
+        default_value = ".",
+        parse(from_os_str)
+    )]
+    pub output_dir: PathBuf,
+
+    #[structopt(
+        help = "The path of each crackme inside the output directory, using {name}, {author}, {id}, {language} and {platform}",
+        long,
+        default_value = "{name}"
+    )]
+    pub template: String,
+
+    #[structopt(
+        help = "What to do when a crackme's directory already exists",
+        long,
+        default_value = "rename",
+        possible_values = Existing::VARIANTS,
+        case_insensitive = true
+    )]
+    pub existing: Existing,
+
+    #[structopt(
+        help = "A password to try on encrypted files (can be repeated, tried before the ones in the config file and the default ones)",
+        long = "password",
+        number_of_values = 1
+    )]
+    pub passwords: Vec<String>,
+
+    #[structopt(
+        help = "Save the original zip as <id>.zip next to the extracted files",
+        long
+    )]
+    pub keep_archive: bool,
+
+    #[structopt(
+        help = "Write a manifest.json with the crackme's details and the hashes of its files",
+        long
+    )]
+    pub manifest: bool,
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct PageArgs {
+    #[structopt(
+        help = "The number of pages to get (starting from the first one)",
+        long,
+        default_value = "1"
+    )]
+    pub pages: u64,
+
+    #[structopt(help = "Get every page", long, conflicts_with = "pages")]
+    pub all: bool,
+
+    #[structopt(
+        help = "The number of pages (and then picked crackmes) to get at once",
+        long,
+        default_value = "4"
+    )]
+    pub jobs: usize,
+}
+
+impl SearchArgs {
+    pub fn into_query(self) -> CrackmeResult<SearchQuery> {
+        let mut query = SearchQuery::new()
+            .difficulty(self.difficulty.0, self.difficulty.1)?
+            .quality(self.quality.0, self.quality.1)?;
+
+        if let Some(name) = self.name {
+            query = query.name(name);
+        }
+
+        if let Some(author) = self.author {
+            query = query.author(author);
+        }
+
+        if let Some(language) = self.language {
+            query = query.language(language);
+        }
+
+        if let Some(platform) = self.platform {
+            query = query.platform(platform);
+        }
+
+        Ok(query)
+    }
+}
+
 fn range_parser(v: &str) -> Result<(u8, u8), &'static str> {
     let mut sides = v.split("..");
 
@@ -71,14 +259,14 @@ fn range_parser(v: &str) -> Result<(u8, u8), &'static str> {
         .zip(sides.next().and_then(|s| s.parse().ok()))
         .ok_or("Invalid bounds")?;
 
-    if sides.next().is_some() || first < 1 || second > 6 {
+    if sides.next().is_some() || first < MIN_RATING || second > MAX_RATING || first > second {
         return Err("Invalid range");
     }
 
     Ok((first, second))
 }
 
-fn id_parser(v: &str) -> Result<String, &'static str> {
+pub fn id_parser(v: &str) -> Result<String, &'static str> {
     if v.len() != 24 {
         Err("Invalid ID length")
     } else {
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use crackmes::cache::{PageCache, PageKind};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, path::PathBuf, time::Duration};
+
+/// Settings read from `<config dir>/cmc/config.toml`, everything is optional
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Tried (after the ones from the command line) on encrypted archive entries
+    pub passwords: Vec<String>,
+
+    /// The keys for picker actions (by their kebab-case names), replacing the default keys of
+    /// each action given
+    pub keys: HashMap<String, Vec<String>>,
+
+    /// Whether the picker uses the mouse, turning it off lets the terminal select text again
+    pub mouse: bool,
+
+    pub cache: CacheConfig,
+}
+
+/// How many seconds each kind of page is cached for before checking if it changed, the library's
+/// defaults are used for the ones not given
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub latest: Option<u64>,
+    pub search: Option<u64>,
+    pub overview: Option<u64>,
+}
+
+impl CacheConfig {
+    /// The cache for crackmes.one's pages, in `<cache dir>/cmc/pages`
+    pub fn page_cache(&self, offline: bool) -> PageCache {
+        let dir = dirs::cache_dir()
+            .unwrap_or_else(env::temp_dir)
+            .join("cmc")
+            .join("pages");
+
+        let ttls = [
+            (PageKind::Latest, self.latest),
+            (PageKind::Search, self.search),
+            (PageKind::Overview, self.overview),
+        ];
+
+        ttls.iter()
+            .filter_map(|&(kind, seconds)| seconds.map(|s| (kind, Duration::from_secs(s))))
+            .fold(
+                PageCache::new(dir).offline(offline),
+                |cache, (kind, ttl)| cache.ttl(kind, ttl),
+            )
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            passwords: Vec::new(),
+            keys: HashMap::new(),
+            mouse: true,
+            cache: CacheConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("cmc").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Config> {
+        let path = match Config::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+
+        let text = fs::read_to_string(&path)?;
+        let config = toml::from_str(&text)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        Ok(config)
+    }
+}
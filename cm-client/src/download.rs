@@ -0,0 +1,116 @@
+use anyhow::{anyhow, Result};
+use crackmes::CrackmesClient;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Where archives are downloaded to before being extracted, unfinished ones are left here so
+/// they can be resumed next time
+pub fn download_dir() -> Result<PathBuf> {
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cmc")
+        .join("downloads");
+
+    fs::create_dir_all(&dir)?;
+
+    Ok(dir)
+}
+
+/// Downloads the crackme's archive (with a progress bar if `progress` is set), carrying on from an
+/// earlier partial download if there is one, and returns the path of the finished file
+pub async fn download_archive(
+    client: &CrackmesClient,
+    id: &str,
+    progress: bool,
+) -> Result<PathBuf> {
+    let dir = download_dir()?;
+    let partial = dir.join(format!("{}.zip.part", id));
+    // the ETag or Last-Modified of the archive `partial` is part of, so it's only resumed if the
+    // archive hasn't changed since
+    let validator = dir.join(format!("{}.zip.part.validator", id));
+    let finished = dir.join(format!("{}.zip", id));
+
+    let offset = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+    let previous = fs::read_to_string(&validator).ok();
+    let mut download = client
+        .download_from(id, offset, previous.as_deref())
+        .await?;
+
+    // without a validator a later run just starts over
+    match download.validator() {
+        Some(current) => fs::write(&validator, current)?,
+        None => remove_if_exists(&validator)?,
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&partial)?;
+
+    // throw away what we had if the server is starting from somewhere else
+    file.set_len(download.offset())?;
+    file.seek(SeekFrom::End(0))?;
+
+    let bar = match download.total() {
+        _ if !progress => ProgressBar::hidden(),
+        Some(total) => ProgressBar::new(total).with_style(
+            ProgressStyle::default_bar()
+                .template("{msg} [{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                .progress_chars("=> "),
+        ),
+        None => ProgressBar::new_spinner().with_style(
+            ProgressStyle::default_spinner().template("{msg} {spinner} {bytes} ({bytes_per_sec})"),
+        ),
+    };
+    bar.set_message(id.to_string());
+    bar.set_position(download.offset());
+
+    while let Some(chunk) = download.chunk().await? {
+        file.write_all(&chunk)?;
+        bar.inc(chunk.len() as u64);
+    }
+
+    file.sync_all()?;
+    bar.finish_and_clear();
+
+    if let Some(total) = download.total() {
+        let len = file.metadata()?.len();
+        if len != total {
+            return Err(anyhow!(
+                "Download of {} stopped at {} of {} bytes, run it again to resume",
+                id,
+                len,
+                total
+            ));
+        }
+    }
+
+    fs::rename(&partial, &finished)?;
+    remove_if_exists(&validator)?;
+
+    Ok(finished)
+}
+
+/// Removes a downloaded archive that turned out to be unusable, so the next run downloads it again
+/// instead of resuming into it
+pub fn discard_archive(id: &str) -> Result<()> {
+    let dir = download_dir()?;
+
+    for name in &["zip", "zip.part", "zip.part.validator"] {
+        remove_if_exists(&dir.join(format!("{}.{}", id, name)))?;
+    }
+
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
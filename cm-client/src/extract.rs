@@ -0,0 +1,589 @@
+use crate::cli::ExtractArgs;
+use anyhow::{anyhow, Result};
+use crackmes::overview::OverviewCrackme;
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    io::{Read, Seek},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+use zip::read::ZipArchive;
+
+// tried after the user's own passwords
+const DEFAULT_PASSWORDS: [&str; 2] = ["crackmes.one", "crackmes.de"];
+
+// the file type bits of a unix mode, and the value they have for symlinks
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+/// What to do when a crackme's directory already exists
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Existing {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+impl Existing {
+    pub const VARIANTS: &'static [&'static str] = &["skip", "overwrite", "rename"];
+}
+
+impl FromStr for Existing {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Existing> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(Existing::Skip),
+            "overwrite" => Ok(Existing::Overwrite),
+            "rename" => Ok(Existing::Rename),
+            _ => Err(anyhow!("Invalid choice for existing directories")),
+        }
+    }
+}
+
+/// What happened to a single entry of the archive
+#[derive(Debug)]
+pub enum Extracted {
+    Dir,
+    File { mode: Option<u32> },
+    Symlink { target: PathBuf },
+    SkippedSymlink { target: PathBuf },
+    Encrypted,
+}
+
+impl fmt::Display for Extracted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Extracted::Dir => write!(f, "created directory"),
+            Extracted::File { mode: Some(mode) } => write!(f, "extracted (mode {:o})", mode),
+            Extracted::File { mode: None } => write!(f, "extracted"),
+            Extracted::Symlink { target } => write!(f, "linked to {}", target.display()),
+            Extracted::SkippedSymlink { target } => write!(
+                f,
+                "skipped symlink to {} (outside of the crackme's directory)",
+                target.display()
+            ),
+            Extracted::Encrypted => write!(f, "couldn't be decrypted with any of the passwords"),
+        }
+    }
+}
+
+/// Where the crackme should be extracted to, `None` if it exists and we were told to skip it (an
+/// existing directory is only replaced by `finish_staging`, once the new one is ready)
+///
+/// `claimed` are the directories other crackmes of the same run are going to, which count as
+/// existing even though they haven't been created yet.
+pub fn crackme_dir(
+    crackme: &OverviewCrackme<'_>,
+    args: &ExtractArgs,
+    claimed: &HashSet<PathBuf>,
+) -> Result<Option<PathBuf>> {
+    let language = crackme.language().to_string();
+    let platform = crackme.platform().to_string();
+
+    let fields = [
+        ("{name}", crackme.name()),
+        ("{author}", crackme.author()),
+        ("{id}", crackme.id()),
+        ("{language}", &language),
+        ("{platform}", &platform),
+    ];
+
+    // every "/" in the template is a directory, but the ones that come from the crackme aren't
+    let mut dir = args.output_dir.clone();
+    for part in args.template.split('/').filter(|p| !p.is_empty()) {
+        let part = fields.iter().fold(part.to_string(), |part, (key, value)| {
+            part.replace(key, &sanitize(value))
+        });
+
+        dir.push(sanitize(&part));
+    }
+
+    // extracting straight into the output directory would mean overwriting all of it
+    if dir == args.output_dir {
+        return Err(anyhow!(
+            "The template {:?} doesn't name a directory for the crackme",
+            args.template
+        ));
+    }
+
+    let exists = |path: &PathBuf| path.exists() || claimed.contains(path);
+    if !exists(&dir) {
+        return Ok(Some(dir));
+    }
+
+    match args.existing {
+        Existing::Skip => Ok(None),
+        // two crackmes of the same run would keep replacing each other
+        Existing::Overwrite if claimed.contains(&dir) => Err(anyhow!(
+            "{} is already where another crackme is going",
+            dir.display()
+        )),
+        Existing::Overwrite => Ok(Some(dir)),
+        Existing::Rename => {
+            let renamed = (1..)
+                .map(|i| {
+                    let mut name = dir.as_os_str().to_owned();
+                    name.push(format!("-{}", i));
+                    PathBuf::from(name)
+                })
+                .find(|path| !exists(path))
+                .unwrap();
+
+            Ok(Some(renamed))
+        }
+    }
+}
+
+/// A new, empty directory next to `dir` to extract into, so `dir` itself is only touched once
+/// everything worked
+pub fn staging_dir(dir: &Path) -> Result<PathBuf> {
+    let staging = sibling(dir, "part")?;
+
+    // left over from a run that was killed
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    Ok(staging)
+}
+
+/// Moves the finished `staging` directory to `dir`, replacing whatever was there before
+pub fn finish_staging(staging: &Path, dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        fs::rename(staging, dir)?;
+        return Ok(());
+    }
+
+    // the old one is moved aside first, so it's never gone without the new one being in place
+    let old = sibling(dir, "old")?;
+    if old.exists() {
+        fs::remove_dir_all(&old)?;
+    }
+
+    fs::rename(dir, &old)?;
+    if let Err(e) = fs::rename(staging, dir) {
+        fs::rename(&old, dir)?;
+        return Err(e.into());
+    }
+
+    fs::remove_dir_all(&old)?;
+
+    Ok(())
+}
+
+// a hidden path next to `dir`, like ".name.part"
+fn sibling(dir: &Path, suffix: &str) -> Result<PathBuf> {
+    let name = dir
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid crackme directory {}", dir.display()))?;
+
+    Ok(dir.with_file_name(format!(".{}.{}", name.to_string_lossy(), suffix)))
+}
+
+// makes a single path component that can't escape its parent or upset any filesystem
+fn sanitize(component: &str) -> String {
+    let cleaned: String = component
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+
+    if cleaned.is_empty() {
+        "_".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+pub fn write_zip_to_disk(
+    archive: impl Read + Seek,
+    root: &Path,
+    args: &ExtractArgs,
+) -> Result<Vec<(PathBuf, Extracted)>> {
+    let mut zip = ZipArchive::new(archive)?;
+
+    let mut report = Vec::new();
+
+    // symlinks are made once everything else is written, so nothing gets written through them
+    let mut symlinks = Vec::new();
+
+    let passwords: Vec<&str> = args
+        .passwords
+        .iter()
+        .map(String::as_str)
+        .chain(DEFAULT_PASSWORDS.iter().copied())
+        .collect();
+
+    // writing the zip file's contents to disk, copied from the zip crates extract method on
+    // ZipArchive
+    for i in 0..zip.len() {
+        // unencrypted files ignore the password, so any of them works for those
+        let mut password = None;
+        for p in &passwords {
+            if zip.by_index_decrypt(i, p.as_bytes())?.is_ok() {
+                password = Some(p);
+                break;
+            }
+        }
+
+        let password = match password {
+            Some(p) => p,
+            None => {
+                let name = zip.by_index_raw(i)?.name().to_string();
+                report.push((root.join(name), Extracted::Encrypted));
+                continue;
+            }
+        };
+
+        let mut file = zip
+            .by_index_decrypt(i, password.as_bytes())?
+            .map_err(|_| anyhow!("Invalid password"))?;
+
+        let filepath = file
+            .enclosed_name()
+            .ok_or_else(|| anyhow!("Invalid file path"))?;
+
+        let outpath = root.join(filepath);
+
+        if file.name().ends_with('/') {
+            fs::create_dir_all(&outpath)?;
+            report.push((outpath, Extracted::Dir));
+            continue;
+        }
+
+        if let Some(p) = outpath.parent() {
+            if !p.exists() {
+                fs::create_dir_all(p)?;
+            }
+        }
+
+        let mode = file.unix_mode();
+
+        if mode.filter(|m| m & S_IFMT == S_IFLNK).is_some() {
+            // the contents of a symlink entry are its target
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            symlinks.push((outpath, PathBuf::from(target)));
+            continue;
+        }
+
+        let mut outfile = fs::File::create(&outpath)?;
+        std::io::copy(&mut file, &mut outfile)?;
+
+        // we never want setuid/setgid/sticky bits from a crackme
+        let mode = mode.map(|m| if args.no_exec { m & 0o666 } else { m & 0o777 });
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
+        }
+
+        report.push((outpath, Extracted::File { mode }));
+    }
+
+    for (link, target) in symlinks {
+        let extracted = create_symlink(root, &link, target)?;
+        report.push((link, extracted));
+    }
+
+    Ok(report)
+}
+
+fn create_symlink(root: &Path, link: &Path, target: PathBuf) -> Result<Extracted> {
+    // where the link points relative to the root, this is `None` if it goes above it
+    let resolved = link
+        .strip_prefix(root)
+        .ok()
+        .and_then(Path::parent)
+        .map(|parent| parent.join(&target))
+        .filter(|_| target.is_relative())
+        .and_then(|path| normalize(&path));
+
+    if resolved.is_none() {
+        return Ok(Extracted::SkippedSymlink { target });
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&target, link)?;
+
+        // links through other links can still escape, so check where it really ends up (links
+        // to files that don't exist can't escape)
+        if let Ok(real) = fs::canonicalize(link) {
+            if !real.starts_with(fs::canonicalize(root)?) {
+                fs::remove_file(link)?;
+                return Ok(Extracted::SkippedSymlink { target });
+            }
+        }
+
+        Ok(Extracted::Symlink { target })
+    }
+
+    #[cfg(not(unix))]
+    Ok(Extracted::SkippedSymlink { target })
+}
+
+// resolves "." and ".." without touching the filesystem, `None` if it goes above the start
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normal = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normal.pop() {
+                    return None;
+                }
+            }
+            c => normal.push(c),
+        }
+    }
+
+    Some(normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{overview, temp_dir};
+    use std::io::{Cursor, Write};
+    use structopt::StructOpt;
+    use zip::{write::FileOptions, ZipWriter};
+
+    // a zip with a single file called `name`
+    fn zip_with(name: &str) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(name, FileOptions::default()).unwrap();
+        zip.write_all(b"hello").unwrap();
+
+        Cursor::new(zip.finish().unwrap().into_inner())
+    }
+
+    // a zip with a single file called `name` with the unix `mode`
+    fn zip_with_mode(name: &str, mode: u32) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().unix_permissions(mode);
+        zip.start_file(name, options).unwrap();
+        zip.write_all(b"#!/bin/sh\n").unwrap();
+        let mut bytes = zip.finish().unwrap().into_inner();
+
+        // the writer drops everything but the permission bits, so the rest (like setuid) is put
+        // back into the external attributes of the central directory entry by hand
+        let entry = bytes.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        let attributes = (0o100000 | mode) << 16;
+        bytes[entry + 38..entry + 42].copy_from_slice(&attributes.to_le_bytes());
+
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn sanitize_cases() {
+        let cases = [
+            ("name", "name"),
+            ("..", "_"),
+            ("../etc", "_etc"),
+            ("a/b\\c", "a_b_c"),
+            ("C:evil", "C_evil"),
+            ("  .hidden. ", "hidden"),
+            ("tab\there", "tab_here"),
+            ("", "_"),
+        ];
+
+        for &(component, sanitized) in &cases {
+            assert_eq!(sanitize(component), sanitized, "{:?}", component);
+        }
+    }
+
+    #[test]
+    fn normalize_cases() {
+        let cases = [
+            ("a/b", Some("a/b")),
+            ("a/./b", Some("a/b")),
+            ("a/../b", Some("b")),
+            ("a/b/../..", Some("")),
+            ("..", None),
+            ("../a", None),
+            ("a/../../b", None),
+        ];
+
+        for &(path, normal) in &cases {
+            assert_eq!(
+                normalize(Path::new(path)),
+                normal.map(PathBuf::from),
+                "{:?}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn entries_cannot_escape() {
+        let args = ExtractArgs::from_iter(&["cmc"]);
+
+        for name in &["../evil.txt", "a/../../evil.txt", "/evil.txt"] {
+            let dir = temp_dir("escape");
+            let root = dir.join("crackme");
+            fs::create_dir(&root).unwrap();
+
+            assert!(
+                write_zip_to_disk(zip_with(name), &root, &args).is_err(),
+                "{}",
+                name
+            );
+            assert!(!dir.join("evil.txt").exists(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn entries_stay_inside() {
+        let args = ExtractArgs::from_iter(&["cmc"]);
+        let root = temp_dir("inside");
+
+        let report = write_zip_to_disk(zip_with("a/./b/../file.txt"), &root, &args).unwrap();
+
+        assert_eq!(report.len(), 1);
+        assert_eq!(fs::read(root.join("a/file.txt")).unwrap(), b"hello");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_are_applied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        for &(no_exec, expected) in &[(false, 0o755), (true, 0o644)] {
+            let root = temp_dir(if no_exec { "no-exec" } else { "exec" });
+            let args = if no_exec {
+                vec!["cmc", "--no-exec"]
+            } else {
+                vec!["cmc"]
+            };
+            let args = ExtractArgs::from_iter(&args);
+
+            let report = write_zip_to_disk(zip_with_mode("run.sh", 0o4755), &root, &args).unwrap();
+            let mode = fs::metadata(root.join("run.sh"))
+                .unwrap()
+                .permissions()
+                .mode();
+
+            assert!(matches!(
+                report.as_slice(),
+                [(_, Extracted::File { mode: Some(m) })] if *m == expected
+            ));
+            assert_eq!(mode & 0o7777, expected, "no_exec: {}", no_exec);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_escape() {
+        let dir = temp_dir("symlinks");
+        let root = dir.join("crackme");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(dir.join("secret"), "secret").unwrap();
+        fs::write(root.join("file"), "file").unwrap();
+
+        let skipped = |target: &str| {
+            let link = root.join("sub").join("link");
+            let _ = fs::remove_file(&link);
+            matches!(
+                create_symlink(&root, &link, PathBuf::from(target)).unwrap(),
+                Extracted::SkippedSymlink { .. }
+            )
+        };
+
+        assert!(skipped("../../secret"));
+        assert!(skipped(dir.join("secret").to_str().unwrap()));
+        assert!(!skipped("../file"));
+        assert!(!skipped("./missing"));
+
+        // a link that is fine on its own but goes through one that leaves the root
+        std::os::unix::fs::symlink(&dir, root.join("out")).unwrap();
+        assert!(skipped("../out/secret"));
+        assert!(!root.join("sub").join("link").exists());
+    }
+
+    fn args_in(dir: &Path, extra: &[&str]) -> ExtractArgs {
+        let mut args = vec!["cmc", "--output-dir", dir.to_str().unwrap()];
+        args.extend(extra);
+
+        ExtractArgs::from_iter(&args)
+    }
+
+    #[test]
+    fn template_must_name_a_directory() {
+        let dir = temp_dir("template");
+        let crackme = overview("../..", "a/b");
+
+        for template in &["", "/", "//"] {
+            let args = args_in(&dir, &["--template", template, "--existing", "overwrite"]);
+            assert!(
+                crackme_dir(&crackme, &args, &HashSet::new()).is_err(),
+                "{:?}",
+                template
+            );
+        }
+
+        let args = args_in(&dir, &["--template", "{author}/{name}"]);
+        assert_eq!(
+            crackme_dir(&crackme, &args, &HashSet::new()).unwrap(),
+            Some(dir.join("a_b").join("_"))
+        );
+    }
+
+    #[test]
+    fn overwrite_waits_for_staging() {
+        let dir = temp_dir("overwrite");
+        let crackme = overview("crackme", "someone");
+        let args = args_in(&dir, &["--existing", "overwrite"]);
+
+        let target = dir.join("crackme");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("old"), "old").unwrap();
+
+        // picking the directory leaves the old one alone
+        assert_eq!(
+            crackme_dir(&crackme, &args, &HashSet::new()).unwrap(),
+            Some(target.clone())
+        );
+        assert!(target.join("old").exists());
+
+        let staging = staging_dir(&target).unwrap();
+        fs::write(staging.join("new"), "new").unwrap();
+        finish_staging(&staging, &target).unwrap();
+
+        assert!(!target.join("old").exists());
+        assert!(target.join("new").exists());
+        assert!(!staging.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn claimed_dirs_count_as_existing() {
+        let dir = temp_dir("claimed");
+        let crackme = overview("crackme", "someone");
+        let claimed: HashSet<_> = vec![dir.join("crackme")].into_iter().collect();
+
+        let pick = |existing: &str| {
+            crackme_dir(
+                &crackme,
+                &args_in(&dir, &["--existing", existing]),
+                &claimed,
+            )
+        };
+
+        assert_eq!(pick("skip").unwrap(), None);
+        assert_eq!(pick("rename").unwrap(), Some(dir.join("crackme-1")));
+        assert!(pick("overwrite").is_err());
+        // nothing is created until the crackme is extracted
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
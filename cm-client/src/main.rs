@@ -1,28 +1,72 @@
 use anyhow::Result;
-use reqwest::Client;
+use crackmes::CrackmesClient;
 use structopt::StructOpt;
 
 mod cli;
+mod config;
+mod download;
+mod extract;
+mod manifest;
 mod mode;
+mod output;
+mod overviews;
+#[cfg(test)]
+mod testing;
 mod tui;
 
 use cli::*;
+use config::Config;
 use mode::*;
+use overviews::OverviewCache;
+use tui::keys::Keymap;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: App = App::from_args();
-    let mut client = Client::builder().cookie_store(true).build()?;
+    let mut args: App = App::from_args();
+    let config = Config::load()?;
+
+    if let Some(extract) = args.nested.extract_args_mut() {
+        extract.passwords.extend(config.passwords);
+    }
+
+    let picker = PickerOptions {
+        keymap: Keymap::new(&config.keys)?,
+        mouse: config.mouse,
+    };
+    let client = CrackmesClient::new()?.cached(config.cache.page_cache(args.offline));
 
     match args.nested {
-        Command::Get { id } => {
-            get::handle_crackme(&mut client, &id).await?;
+        Command::Get {
+            ids,
+            from_file,
+            jobs,
+            extract,
+        } => {
+            let ids = get::collect_ids(ids, from_file.as_deref())?;
+            get::handle_crackmes(&client, &OverviewCache::default(), &ids, jobs, &extract).await?;
+        }
+        Command::ClearCache => {
+            let removed = config.cache.page_cache(false).clear()?;
+            println!("Removed {} cached pages", removed);
+        }
+        Command::Info { id, json } => {
+            info::handle_info(&client, &id, json).await?;
         }
-        Command::Search(args) => {
-            search::handle_search_results(&mut client, args).await?;
+        Command::Search {
+            args,
+            pages,
+            format,
+            extract,
+        } => {
+            search::handle_search_results(&client, args, &pages, format, &extract, picker).await?;
         }
-        Command::Latest { page } => {
-            latest::handle_latest_results(&mut client, page).await?;
+        Command::Latest {
+            page,
+            pages,
+            format,
+            extract,
+        } => {
+            latest::handle_latest_results(&client, page, &pages, format, &extract, picker).await?;
         }
     }
     Ok(())
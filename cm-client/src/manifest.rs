@@ -0,0 +1,146 @@
+use crate::extract::Extracted;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use crackmes::overview::OverviewCrackme;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// A record of what was downloaded, so everyone can check they have the same files
+#[derive(Serialize, Debug)]
+pub struct Manifest<'a> {
+    crackme: &'a OverviewCrackme<'a>,
+    downloaded: DateTime<Utc>,
+    archive: FileHash,
+    files: Vec<FileHash>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FileHash {
+    path: PathBuf,
+    size: u64,
+    sha256: String,
+}
+
+impl<'a> Manifest<'a> {
+    pub fn new(
+        crackme: &'a OverviewCrackme<'a>,
+        downloaded: DateTime<Utc>,
+        archive: &Path,
+        root: &Path,
+        report: &[(PathBuf, Extracted)],
+    ) -> Result<Manifest<'a>> {
+        let archive = hash_file(archive, format!("{}.zip", crackme.id()).into())?;
+
+        let files = report
+            .iter()
+            .filter(|(_, extracted)| matches!(extracted, Extracted::File { .. }))
+            .map(|(path, _)| hash_file(path, path.strip_prefix(root).unwrap_or(path).into()))
+            .collect::<Result<_>>()?;
+
+        let manifest = Manifest {
+            crackme,
+            downloaded,
+            archive,
+            files,
+        };
+
+        Ok(manifest)
+    }
+
+    pub fn write(&self, root: &Path) -> Result<()> {
+        let file = fs::File::create(root.join(MANIFEST_NAME))?;
+        serde_json::to_writer_pretty(file, self)?;
+
+        Ok(())
+    }
+}
+
+// `name` is the path the file is recorded under
+fn hash_file(path: &Path, name: PathBuf) -> Result<FileHash> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)?;
+
+    let hash = FileHash {
+        path: name,
+        size,
+        sha256: hex::encode(hasher.finalize()),
+    };
+
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{overview, temp_dir};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn manifest_hashes_files() {
+        let dir = temp_dir("manifest");
+        let root = dir.join("crackme");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("hello.txt"), "hello").unwrap();
+        fs::write(root.join("empty"), "").unwrap();
+        let archive = dir.join("archive.zip");
+        fs::write(&archive, "abc").unwrap();
+
+        let report = vec![
+            (root.join("sub"), Extracted::Dir),
+            (
+                root.join("sub").join("hello.txt"),
+                Extracted::File { mode: None },
+            ),
+            (root.join("empty"), Extracted::File { mode: Some(0o644) }),
+            (root.join("locked.txt"), Extracted::Encrypted),
+        ];
+
+        let crackme = overview("crackme", "someone");
+        let downloaded: DateTime<Utc> = "2021-05-07T17:40:00Z".parse().unwrap();
+        Manifest::new(&crackme, downloaded, &archive, &root, &report)
+            .unwrap()
+            .write(&root)
+            .unwrap();
+
+        let text = fs::read_to_string(root.join(MANIFEST_NAME)).unwrap();
+        let manifest: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(manifest["downloaded"], "2021-05-07T17:40:00Z");
+        assert_eq!(
+            manifest["archive"],
+            json!({
+                "path": "60957b9a33c5d458ce0ec88e.zip",
+                "size": 3,
+                "sha256": "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            })
+        );
+        // only files, recorded relative to the crackme's directory
+        assert_eq!(
+            manifest["files"],
+            json!([
+                {
+                    "path": "sub/hello.txt",
+                    "size": 5,
+                    "sha256": "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+                },
+                {
+                    "path": "empty",
+                    "size": 0,
+                    "sha256": "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                },
+            ])
+        );
+
+        // the crackme reads back as it was, which it can't if any of its keys are repeated
+        let read: OverviewCrackme<'_> =
+            serde_json::from_value(manifest["crackme"].clone()).unwrap();
+        assert_eq!(read, crackme);
+    }
+}
@@ -1,100 +1,224 @@
+use crate::{
+    cli::{self, ExtractArgs},
+    download,
+    extract::{self, Extracted},
+    manifest::Manifest,
+    overviews::OverviewCache,
+};
 use anyhow::{anyhow, Result};
-use crackmes::{overview::OverviewCrackme, Html};
-use reqwest::Client;
-use std::{fs, io::Cursor, path::Path};
-use zip::read::ZipArchive;
-
-const MAIN_URL: &str = "https://crackmes.one";
-const GET_URL: &str = "https://crackmes.one/crackme/";
-
-fn write_zip_to_disk(bytes: Vec<u8>, crackme: &OverviewCrackme<'_>) -> Result<()> {
-    // wrap our bytes with a cursor for the seek implementation
-    let mut zip = ZipArchive::new(Cursor::new(bytes))?;
-
-    // writing the zip file's contents to disk, copied from the zip crates extract method on
-    // ZipArchive
-    for i in 0..zip.len() {
-        let first = zip.by_index_decrypt(i, b"crackmes.one")?;
-        let mut file = match first {
-            Ok(f) => f,
-            Err(_) => {
-                drop(first);
-                match zip.by_index_decrypt(i, b"crackmes.de")? {
-                    Ok(f) => f,
-                    Err(_) => continue,
-                }
-            }
-        };
-
-        let filepath = file
-            .enclosed_name()
-            .ok_or_else(|| anyhow!("Invalid file path"))?;
-
-        let outpath = Path::new(crackme.name()).join(filepath);
-
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath)?;
-        } else {
-            if let Some(p) = outpath.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(&p)?;
-                }
-            }
-            let mut outfile = fs::File::create(&outpath)?;
-            std::io::copy(&mut file, &mut outfile)?;
+use chrono::{DateTime, Utc};
+use crackmes::{overview::OverviewCrackme, CrackmesClient};
+use crossterm::tty::IsTty;
+use futures_util::stream::{self, StreamExt};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The IDs from the command line followed by the ones in `from_file`, or stdin if there are none
+pub fn collect_ids(mut ids: Vec<String>, from_file: Option<&Path>) -> Result<Vec<String>> {
+    let reader: Box<dyn BufRead> = match from_file {
+        Some(path) if path != Path::new("-") => Box::new(BufReader::new(fs::File::open(path)?)),
+        Some(_) => Box::new(BufReader::new(io::stdin())),
+        None if ids.is_empty() && !io::stdin().is_tty() => Box::new(BufReader::new(io::stdin())),
+        None if ids.is_empty() => return Err(anyhow!("No crackme IDs given")),
+        None => return Ok(ids),
+    };
+
+    // blank lines and "#" comments are allowed so the lists can be annotated
+    for line in reader.lines() {
+        let line = line?;
+        let id = line.split('#').next().unwrap_or_default().trim();
+
+        if !id.is_empty() {
+            ids.push(cli::id_parser(id).map_err(|e| anyhow!("{}: {}", e, id))?);
         }
     }
 
-    Ok(())
-}
+    if ids.is_empty() {
+        return Err(anyhow!("No crackme IDs given"));
+    }
 
-pub async fn get_description(client: &mut Client, id: &str) -> Result<String> {
-    // downloads crackme page
-    let html = {
-        let body = client
-            .get(GET_URL.to_string() + id)
-            .send()
-            .await?
-            .text()
-            .await?;
-
-        Html::parse_document(&body)
-    };
+    Ok(ids)
+}
 
-    // this unwrap is safe because OverviewCrackMe is guaranteed to have a description
-    Ok(OverviewCrackme::with_full_html(&html, id)?
-        .description()
-        .to_string())
+// what happened to a crackme that didn't fail
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Got {
+    Extracted,
+    // its directory already existed and we were told to skip it
+    Skipped,
 }
 
-pub async fn handle_crackme(client: &mut Client, id: &str) -> Result<()> {
-    // downloads crackme page
-    let html = {
-        let body = client
-            .get(GET_URL.to_string() + id)
-            .send()
-            .await?
-            .text()
-            .await?;
-
-        Html::parse_document(&body)
-    };
+/// Gets every crackme, `jobs` at a time, and fails if any of them did (overviews already in
+/// `overviews` aren't downloaded again, and IDs given more than once are only got once)
+pub async fn handle_crackmes(
+    client: &CrackmesClient,
+    overviews: &OverviewCache,
+    ids: &[String],
+    jobs: usize,
+    args: &ExtractArgs,
+) -> Result<()> {
+    // the same crackme twice at once would download into the same file
+    let mut seen = HashSet::new();
+    let ids: Vec<&String> = ids.iter().filter(|id| seen.insert(*id)).collect();
+
+    match ids[..] {
+        [] => return Ok(()),
+        [id] => {
+            let crackme = overviews.fetch(client, id).await?;
+            return handle_crackme(client, &crackme, args).await;
+        }
+        _ => {}
+    }
+
+    let fetched: Vec<(&String, Result<Arc<OverviewCrackme<'static>>>)> = stream::iter(ids)
+        .map(|id| async move { (id, overviews.fetch(client, id).await) })
+        .buffered(jobs.max(1))
+        .collect()
+        .await;
+
+    // the directories are picked one at a time, so two crackmes can't both pick the same one
+    // before either of them has created it
+    let mut claimed = HashSet::new();
+    let mut planned = Vec::with_capacity(fetched.len());
+    for (id, crackme) in fetched {
+        let plan = crackme.and_then(|crackme| {
+            let dir = extract::crackme_dir(&crackme, args, &claimed)?;
+            claimed.extend(dir.clone());
+            Ok((crackme, dir))
+        });
+        planned.push((id, plan));
+    }
+
+    // progress bars from several downloads would draw over each other
+    let results: Vec<(&String, Result<Got>)> = stream::iter(planned)
+        .map(|(id, plan)| async move {
+            let result = match plan {
+                Ok((crackme, Some(dir))) => get_crackme(client, &crackme, &dir, args, false)
+                    .await
+                    .map(|()| Got::Extracted),
+                Ok((_, None)) => Ok(Got::Skipped),
+                Err(e) => Err(e),
+            };
+            (id, result)
+        })
+        .buffer_unordered(jobs.max(1))
+        .collect()
+        .await;
+
+    println!();
+    for (id, result) in &results {
+        match result {
+            Ok(Got::Extracted) => println!("{}: ok", id),
+            Ok(Got::Skipped) => println!("{}: skipped, its directory already exists", id),
+            Err(e) => println!("{}: failed: {:#}", id, e),
+        }
+    }
+
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    if failed > 0 {
+        return Err(anyhow!("{} of {} crackmes failed", failed, results.len()));
+    }
 
-    let crackme = OverviewCrackme::with_full_html(&html, id)?;
+    Ok(())
+}
 
+/// Downloads and extracts a crackme whose overview has already been fetched
+pub async fn handle_crackme(
+    client: &CrackmesClient,
+    crackme: &OverviewCrackme<'_>,
+    args: &ExtractArgs,
+) -> Result<()> {
+    match extract::crackme_dir(crackme, args, &HashSet::new())? {
+        Some(dir) => get_crackme(client, crackme, &dir, args, true).await,
+        None => {
+            println!("{} already exists, skipping", crackme.name());
+            Ok(())
+        }
+    }
+}
+
+// downloads the crackme and extracts it into `dir`
+async fn get_crackme(
+    client: &CrackmesClient,
+    crackme: &OverviewCrackme<'_>,
+    dir: &Path,
+    args: &ExtractArgs,
+    progress: bool,
+) -> Result<()> {
     // getting the zip file
-    let bytes = client
-        .get(MAIN_URL.to_string() + "/static/crackme/" + crackme.id() + ".zip")
-        .send()
-        .await?
-        .bytes()
-        .await?
-        .to_vec();
-
-    // writing the files contained inside it to disk (in a new folder in the current directory with
-    // its name being the id)
-    write_zip_to_disk(bytes, &crackme)?;
+    let archive = download::download_archive(client, crackme.id(), progress).await?;
+    let downloaded = Utc::now();
+
+    // everything goes into a staging directory first, so a failure leaves an existing `dir` as it
+    // was and doesn't leave a half extracted one behind
+    let staging = extract::staging_dir(dir)?;
+    let report = match stage(crackme, &archive, downloaded, &staging, args) {
+        Ok(report) => report,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            // most likely a broken archive, which would fail the same way if it was kept
+            let _ = download::discard_archive(crackme.id());
+            return Err(e);
+        }
+    };
+    extract::finish_staging(&staging, dir)?;
+    fs::remove_file(&archive)?;
+
+    // the report points into `staging`, which is `dir` now
+    let report: Vec<(PathBuf, Extracted)> = report
+        .into_iter()
+        .map(|(path, extracted)| {
+            let path = dir.join(path.strip_prefix(&staging).unwrap_or(&path));
+            (path, extracted)
+        })
+        .collect();
+
     println!("{}", crackme);
+    for (path, extracted) in &report {
+        println!("{}: {}", path.display(), extracted);
+    }
+
+    let failed: Vec<String> = report
+        .iter()
+        .filter(|(_, extracted)| matches!(extracted, Extracted::Encrypted))
+        .map(|(path, _)| path.display().to_string())
+        .collect();
+
+    if !failed.is_empty() {
+        return Err(anyhow!(
+            "Couldn't decrypt {} file(s), try adding their password with --password: {}",
+            failed.len(),
+            failed.join(", ")
+        ));
+    }
 
     Ok(())
 }
+
+// writes the files, manifest and archive copy of the crackme into `staging`
+fn stage(
+    crackme: &OverviewCrackme<'_>,
+    archive: &Path,
+    downloaded: DateTime<Utc>,
+    staging: &Path,
+    args: &ExtractArgs,
+) -> Result<Vec<(PathBuf, Extracted)>> {
+    // writing the files contained inside it to disk
+    let report = extract::write_zip_to_disk(fs::File::open(archive)?, staging, args)?;
+
+    if args.manifest {
+        Manifest::new(crackme, downloaded, archive, staging, &report)?.write(staging)?;
+    }
+
+    if args.keep_archive {
+        // the download directory might be on another filesystem, so no renaming
+        fs::copy(archive, staging.join(format!("{}.zip", crackme.id())))?;
+    }
+
+    Ok(report)
+}
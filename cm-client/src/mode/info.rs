@@ -0,0 +1,29 @@
+use anyhow::Result;
+use crackmes::CrackmesClient;
+
+pub async fn handle_info(client: &CrackmesClient, id: &str, json: bool) -> Result<()> {
+    let crackme = client.overview(id).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&crackme)?);
+        return Ok(());
+    }
+
+    println!("{}", crackme);
+
+    if !crackme.solutions().is_empty() {
+        println!("Solutions:");
+        for solution in crackme.solutions() {
+            println!("{}", solution);
+        }
+    }
+
+    if !crackme.comments().is_empty() {
+        println!("Comments:");
+        for comment in crackme.comments() {
+            println!("{}", comment);
+        }
+    }
+
+    Ok(())
+}
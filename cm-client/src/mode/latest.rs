@@ -1,31 +1,34 @@
-use crate::mode::{self, get};
-use crackmes::{
-    list::{self, ListCrackme},
-    Html,
+use crate::{
+    cli::{ExtractArgs, PageArgs},
+    mode::{self, get, PickerOptions},
+    output::{self, Format},
+    overviews::OverviewCache,
 };
+use crackmes::{list::ListCrackme, CrackmesClient};
 
 use anyhow::Result;
-use reqwest::Client;
 
-const LATEST_URL: &str = "https://crackmes.one/lasts/";
+pub async fn handle_latest_results(
+    client: &CrackmesClient,
+    number: u64,
+    pages: &PageArgs,
+    format: Option<Format>,
+    extract: &ExtractArgs,
+    picker: PickerOptions,
+) -> Result<()> {
+    let mut crackmes: Vec<ListCrackme<'static>> =
+        mode::fetch_pages(number, pages, |page| client.latest_page(page)).await?;
 
-pub async fn handle_latest_results<'a>(client: &mut Client, number: u64) -> Result<()> {
-    let html = {
-        let body = client
-            .get(format!("{}{}", LATEST_URL, number))
-            .send()
-            .await?
-            .text()
-            .await?;
-        Html::parse_document(&body)
-    };
-
-    let latest = html;
+    if let Some(format) = output::plain_format(format) {
+        return output::print_list(&crackmes, format);
+    }
 
-    let mut crackmes: Vec<ListCrackme<'_>> = list::parse_list(&latest)?;
+    // the overviews the preview fetched are reused for the download
+    let overviews = OverviewCache::default();
 
-    if let Some(crackme) = mode::get_choice(client, &mut crackmes).await? {
-        get::handle_crackme(client, crackme.id()).await?;
+    if let Some(chosen) = mode::get_choice(client, &overviews, &mut crackmes, picker).await? {
+        let ids: Vec<String> = chosen.iter().map(|c| c.id().to_string()).collect();
+        get::handle_crackmes(client, &overviews, &ids, pages.jobs, extract).await?;
     }
 
     Ok(())
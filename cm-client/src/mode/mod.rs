@@ -1,63 +1,225 @@
-use crate::tui::{
-    search::{SearchText, Searcher},
-    term,
+use crate::{
+    cli::PageArgs,
+    overviews::OverviewCache,
+    tui::{
+        input::{self, SearchText},
+        keys::{Action, Input, Keymap},
+        prefetch::Prefetcher,
+        search::Searcher,
+        term,
+    },
 };
 
 use anyhow::Result;
-use crackmes::list::ListCrackme;
-use crossterm::event::{Event, EventStream, KeyModifiers};
-use futures_util::stream::StreamExt;
-use reqwest::Client;
+use crackmes::{
+    error::CrackmeResult,
+    list::{ListCrackme, ListPage},
+    CrackmesClient,
+};
+use crossterm::event::{Event, EventStream, KeyCode, MouseButton, MouseEventKind};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+// how close together two clicks on the same row have to be to count as a double click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// How the picker behaves, from the config file
+#[derive(Debug)]
+pub struct PickerOptions {
+    pub keymap: Keymap,
+    pub mouse: bool,
+}
 
 pub mod get;
+pub mod info;
 pub mod latest;
 pub mod search;
 
+// gets the `first` page and the ones after it that were asked for, merged in page order
+pub async fn fetch_pages<F, Fut>(
+    first: u64,
+    pages: &PageArgs,
+    fetch: F,
+) -> Result<Vec<ListCrackme<'static>>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = CrackmeResult<ListPage<'static>>>,
+{
+    // the first page tells us how many there are
+    let ListPage {
+        mut crackmes,
+        last_page,
+    } = fetch(first).await?;
+
+    let last_page = last_page.unwrap_or(first).max(first);
+    let last = if pages.all {
+        last_page
+    } else {
+        last_page.min(first + pages.pages.saturating_sub(1))
+    };
+
+    let rest: Vec<ListPage<'static>> = stream::iter(first + 1..=last)
+        .map(&fetch)
+        .buffered(pages.jobs.max(1))
+        .try_collect()
+        .await?;
+
+    for page in rest {
+        crackmes.extend(page.crackmes);
+    }
+
+    Ok(crackmes)
+}
+
 // TODO: Optimize this
+// returns `None` if the user backed out, otherwise the marked crackmes (or the selected one)
 pub async fn get_choice<'a>(
-    client: &mut Client,
-    input: &'a mut [ListCrackme<'a>],
-) -> Result<Option<&'a ListCrackme<'a>>> {
-    let mut term = term::get_term()?;
+    client: &CrackmesClient,
+    overviews: &OverviewCache,
+    input: &'a mut [ListCrackme<'static>],
+    options: PickerOptions,
+) -> Result<Option<Vec<&'a ListCrackme<'static>>>> {
+    let PickerOptions { mut keymap, mouse } = options;
+    let mut term = term::get_term(mouse)?;
 
     let mut events = EventStream::new();
 
     let mut searcher = Searcher::new(input);
-    let mut search_text = SearchText::default();
+    // a history that can't be read just starts empty
+    let mut search_text = SearchText::with_history(input::load_history().unwrap_or_default());
 
-    searcher.fetch_descriptions(client).await?;
+    let mut prefetcher = Prefetcher::new(client.clone(), overviews.clone());
+    prefetcher.request(searcher.wanted_overviews());
 
-    term::draw(&mut term, &search_text, &mut searcher)?;
+    term::draw(&mut term, &search_text, &mut searcher, &keymap)?;
 
-    while let Some(e) = events.next().await.transpose()? {
-        if let Event::Key(k) = e {
-            use crossterm::event::KeyCode::*;
-            match k.code {
-                Enter => {
-                    break;
+    // the row and time of the last click, to spot double clicks
+    let mut last_click: Option<(usize, Instant)> = None;
+    let mut last_search = String::new();
+
+    // whether the user picked something instead of backing out
+    let confirmed = loop {
+        let e = tokio::select! {
+            e = events.next() => match e.transpose()? {
+                Some(e) => e,
+                None => break true,
+            },
+            Some((i, overview)) = prefetcher.next() => {
+                searcher.set_overview(i, overview);
+                term::draw(&mut term, &search_text, &mut searcher, &keymap)?;
+                continue;
+            }
+        };
+
+        if let Event::Mouse(m) = e {
+            match m.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    if let Some(row) = searcher.row_at(m.column, m.row) {
+                        let now = Instant::now();
+                        let double = last_click
+                            .filter(|&(r, at)| r == row && now - at < DOUBLE_CLICK)
+                            .is_some();
+
+                        searcher.select(row);
+                        if double {
+                            break true;
+                        }
+                        last_click = Some((row, now));
+                    }
                 }
-                Esc => return Ok(None),
-                Up => searcher.previous(),
-                Char('k') if k.modifiers == KeyModifiers::CONTROL => searcher.previous(),
-                Down => searcher.next(),
-                Char('j') if k.modifiers == KeyModifiers::CONTROL => searcher.next(),
-                Char(c) => {
-                    search_text.push(c);
-                    searcher.search(search_text.as_str());
+                MouseEventKind::ScrollDown if searcher.in_preview(m.column, m.row) => {
+                    searcher.scroll_preview(1)
                 }
-                Backspace => {
-                    search_text.pop();
-                    searcher.search(search_text.as_str())
+                MouseEventKind::ScrollUp if searcher.in_preview(m.column, m.row) => {
+                    searcher.scroll_preview(-1)
                 }
-                _ => break,
+                MouseEventKind::ScrollDown => searcher.next(),
+                MouseEventKind::ScrollUp => searcher.previous(),
+                _ => {}
             }
-            searcher.fetch_descriptions(client).await?;
+            prefetcher.request(searcher.wanted_overviews());
         }
 
-        term::draw(&mut term, &search_text, &mut searcher)?;
-    }
+        if let Event::Key(k) = e {
+            // the help closes on any key, without it doing anything else
+            if searcher.show_help() {
+                searcher.toggle_help();
+                term::draw(&mut term, &search_text, &mut searcher, &keymap)?;
+                continue;
+            }
+
+            let page = searcher.page_size() as isize;
+            let typing = !search_text.as_str().is_empty();
+
+            match keymap.feed(k.into(), typing) {
+                Input::Action(action) => match action {
+                    Action::Confirm => break true,
+                    Action::Cancel => break false,
+                    Action::Up => searcher.previous(),
+                    Action::Down => searcher.next(),
+                    Action::PageUp => searcher.move_by(-page),
+                    Action::PageDown => searcher.move_by(page),
+                    Action::HalfPageUp => searcher.move_by(-page / 2),
+                    Action::HalfPageDown => searcher.move_by(page / 2),
+                    Action::Top => searcher.first(),
+                    Action::Bottom => searcher.last(),
+                    Action::Mark => searcher.toggle_mark(),
+                    Action::Help => searcher.toggle_help(),
+                    Action::CursorLeft => search_text.left(),
+                    Action::CursorRight => search_text.right(),
+                    Action::CursorStart => search_text.home(),
+                    Action::CursorEnd => search_text.end(),
+                    Action::DeleteWord => search_text.delete_word(),
+                    Action::ClearSearch => search_text.clear(),
+                    Action::HistoryPrevious => search_text.history_previous(),
+                    Action::HistoryNext => search_text.history_next(),
+                    Action::NextTab => searcher.switch_tab(1),
+                    Action::PreviousTab => searcher.switch_tab(-1),
+                    Action::ScrollPreviewDown => searcher.scroll_preview(1),
+                    Action::ScrollPreviewUp => searcher.scroll_preview(-1),
+                    sort => {
+                        if let Some(column) = sort.sort_column() {
+                            searcher.sort(column);
+                        }
+                    }
+                },
+                Input::Pending => {}
+                // anything that isn't bound either edits the search or is ignored
+                Input::Unbound(keys) => {
+                    for key in keys {
+                        match (key.text(), key.code()) {
+                            (Some(c), _) => search_text.insert(c),
+                            (None, KeyCode::Backspace) => search_text.backspace(),
+                            (None, KeyCode::Delete) => search_text.delete(),
+                            _ => {}
+                        }
+                    }
+                }
+            }
 
-    term::close_term(term)?;
+            // moving the cursor doesn't need a new search
+            if search_text.as_str() != last_search {
+                last_search = search_text.as_str().to_string();
+                searcher.search(&last_search);
+            }
+            prefetcher.request(searcher.wanted_overviews());
+        }
+
+        term::draw(&mut term, &search_text, &mut searcher, &keymap)?;
+    };
+
+    // the terminal is restored when `term` is dropped
+    drop(term);
+
+    // not remembering the search isn't worth failing over, and it's kept even when backing out
+    let _ = input::save_history(&search_text.into_history());
+
+    if !confirmed {
+        return Ok(None);
+    }
 
-    Ok(searcher.into_selected())
+    Ok(Some(searcher.into_chosen()))
 }
@@ -1,72 +1,41 @@
 use crate::{
-    cli::SearchArgs,
-    mode::{self, get},
+    cli::{ExtractArgs, PageArgs, SearchArgs},
+    mode::{self, get, PickerOptions},
+    output::{self, Format},
+    overviews::OverviewCache,
 };
-use crackmes::{
-    list::{self, ListCrackme},
-    Html, Selector,
-};
-
-use anyhow::{anyhow, Result};
-use reqwest::Client;
+use crackmes::{list::ListCrackme, CrackmesClient};
 
-const SEARCH_URL: &str = "https://crackmes.one/search";
+use anyhow::Result;
 
 // returns all the search results
-pub async fn handle_search_results<'a>(client: &mut Client, args: SearchArgs) -> Result<()> {
-    let html = {
-        let body = client.get(SEARCH_URL).send().await?.text().await?;
-        Html::parse_document(&body)
-    };
-
-    let token = get_token(&html)?;
-
-    let mut params = vec![
-        ("name", args.name.unwrap_or_default()),
-        ("author", args.author.unwrap_or_default()),
-        ("difficulty-min", args.difficulty.0.to_string()),
-        ("difficulty-max", args.difficulty.1.to_string()),
-        ("quality-min", args.quality.0.to_string()),
-        ("quality-max", args.quality.1.to_string()),
-        ("token", token.to_string()),
-    ];
-
-    if let Some(l) = args.language {
-        params.push(("lang", l.to_string()));
-    }
-
-    if let Some(p) = args.platform {
-        params.push(("platform", p.to_string()));
+pub async fn handle_search_results(
+    client: &CrackmesClient,
+    args: SearchArgs,
+    pages: &PageArgs,
+    format: Option<Format>,
+    extract: &ExtractArgs,
+    picker: PickerOptions,
+) -> Result<()> {
+    let query = args.into_query()?;
+
+    let mut crackmes: Vec<ListCrackme<'static>> = mode::fetch_pages(1, pages, |page| {
+        let query = query.clone().page(page);
+        async move { client.search_page(&query).await }
+    })
+    .await?;
+
+    if let Some(format) = output::plain_format(format) {
+        return output::print_list(&crackmes, format);
     }
 
-    let search = client
-        .post(SEARCH_URL)
-        .form(&params)
-        .send()
-        .await?
-        .text()
-        .await?;
+    // the overviews the preview fetched are reused for the download
+    let overviews = OverviewCache::default();
 
-    let search = Html::parse_document(&search);
-
-    let mut crackmes: Vec<ListCrackme<'_>> = list::parse_list(&search)?;
-
-    if let Some(crackme) = mode::get_choice(client, &mut crackmes).await? {
-        get::handle_crackme(client, crackme.id()).await?;
+    if let Some(chosen) = mode::get_choice(client, &overviews, &mut crackmes, picker).await? {
+        let ids: Vec<String> = chosen.iter().map(|c| c.id().to_string()).collect();
+        get::handle_crackmes(client, &overviews, &ids, pages.jobs, extract).await?;
     }
 
     Ok(())
 }
-
-// returns the token to allow searching
-fn get_token(html: &Html) -> Result<&str> {
-    let selector = Selector::parse("#token").unwrap();
-
-    let token = html
-        .select(&selector)
-        .next()
-        .and_then(|t| t.value().attr("value"))
-        .ok_or_else(|| anyhow!("Couldn't parse token"))?;
-
-    Ok(token)
-}
@@ -0,0 +1,218 @@
+use anyhow::{anyhow, Result};
+use crackmes::list::ListCrackme;
+use crossterm::tty::IsTty;
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// How crackme listings are printed when we aren't using the TUI
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Format {
+    Table,
+    Json,
+    Jsonl,
+    Csv,
+    Ids,
+}
+
+impl Format {
+    pub const VARIANTS: &'static [&'static str] = &["table", "json", "jsonl", "csv", "ids"];
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Format> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "jsonl" => Ok(Format::Jsonl),
+            "csv" => Ok(Format::Csv),
+            "ids" => Ok(Format::Ids),
+            _ => Err(anyhow!("Invalid format")),
+        }
+    }
+}
+
+/// The format to print in, or `None` if we should let the user pick with the TUI
+pub fn plain_format(format: Option<Format>) -> Option<Format> {
+    // the TUI is useless when piping, so print a table instead
+    format.or_else(|| {
+        if io::stdout().is_tty() {
+            None
+        } else {
+            Some(Format::Table)
+        }
+    })
+}
+
+const HEADERS: [&str; 10] = [
+    "ID",
+    "Name",
+    "Author",
+    "Language",
+    "Platform",
+    "Difficulty",
+    "Quality",
+    "Date",
+    "Solutions",
+    "Comments",
+];
+
+fn row(crackme: &ListCrackme<'_>) -> [String; 10] {
+    [
+        crackme.id().to_string(),
+        crackme.name().to_string(),
+        crackme.author().to_string(),
+        crackme.language().to_string(),
+        crackme.platform().to_string(),
+        format!("{:.1}", crackme.stats().difficulty),
+        format!("{:.1}", crackme.stats().quality),
+        crackme.date().to_string(),
+        crackme.solutions().to_string(),
+        crackme.comments().to_string(),
+    ]
+}
+
+pub fn print_list(crackmes: &[ListCrackme<'_>], format: Format) -> Result<()> {
+    let stdout = io::stdout();
+    write_list(&mut stdout.lock(), crackmes, format)
+}
+
+fn write_list(out: &mut impl Write, crackmes: &[ListCrackme<'_>], format: Format) -> Result<()> {
+    match format {
+        Format::Table => write_table(out, crackmes)?,
+        Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(crackmes)?)?,
+        Format::Jsonl => {
+            for crackme in crackmes {
+                writeln!(out, "{}", serde_json::to_string(crackme)?)?;
+            }
+        }
+        Format::Csv => {
+            writeln!(out, "{}", HEADERS.join(","))?;
+            for crackme in crackmes {
+                let fields: Vec<String> = row(crackme).iter().map(|f| csv_escape(f)).collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+        }
+        Format::Ids => {
+            for crackme in crackmes {
+                writeln!(out, "{}", crackme.id())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_table(out: &mut impl Write, crackmes: &[ListCrackme<'_>]) -> Result<()> {
+    let rows: Vec<[String; 10]> = crackmes.iter().map(row).collect();
+
+    let mut widths = [0; 10];
+    for (i, header) in HEADERS.iter().enumerate() {
+        widths[i] = rows
+            .iter()
+            .map(|r| r[i].chars().count())
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or_default();
+    }
+
+    let line = |fields: &[&str]| {
+        fields
+            .iter()
+            .zip(widths.iter())
+            .map(|(f, &w)| format!("{:<w$}", f, w = w))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    writeln!(out, "{}", line(&HEADERS).trim_end())?;
+    for r in &rows {
+        let fields: Vec<&str> = r.iter().map(String::as_str).collect();
+        writeln!(out, "{}", line(&fields).trim_end())?;
+    }
+
+    Ok(())
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::crackme;
+    use serde_json::json;
+
+    fn written(crackmes: &[ListCrackme<'_>], format: Format) -> String {
+        let mut out = Vec::new();
+        write_list(&mut out, crackmes, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_fields() {
+        let crackmes = [crackme(
+            json!({ "name": "say \"hi\", then run", "author": "a,b" }),
+        )];
+
+        assert_eq!(
+            written(&crackmes, Format::Csv),
+            "ID,Name,Author,Language,Platform,Difficulty,Quality,Date,Solutions,Comments\n\
+             60957b9a33c5d458ce0ec88e,\"say \"\"hi\"\", then run\",\"a,b\",C/C++,Windows,2.0,3.0,\
+             5:40 PM 05/07/2021,0,0\n"
+        );
+    }
+
+    #[test]
+    fn csv_escape_cases() {
+        let cases = [
+            ("plain", "plain"),
+            ("a,b", "\"a,b\""),
+            ("a \"b\"", "\"a \"\"b\"\"\""),
+            ("two\nlines", "\"two\nlines\""),
+            ("", ""),
+        ];
+
+        for &(field, escaped) in &cases {
+            assert_eq!(csv_escape(field), escaped, "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn jsonl_is_one_crackme_per_line() {
+        let crackmes = [
+            crackme(json!({ "id": "60957b9a33c5d458ce0ec88e", "name": "one" })),
+            crackme(json!({ "id": "60957b9a33c5d458ce0ec88f", "name": "two,\n\"three\"" })),
+        ];
+
+        let text = written(&crackmes, Format::Jsonl);
+        let lines: Vec<ListCrackme<'static>> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines, crackmes);
+    }
+
+    #[test]
+    fn ids_are_one_per_line() {
+        let crackmes = [
+            crackme(json!({ "id": "60957b9a33c5d458ce0ec88e" })),
+            crackme(json!({ "id": "60957b9a33c5d458ce0ec88f" })),
+        ];
+
+        assert_eq!(
+            written(&crackmes, Format::Ids),
+            "60957b9a33c5d458ce0ec88e\n60957b9a33c5d458ce0ec88f\n"
+        );
+        assert_eq!(written(&[], Format::Ids), "");
+    }
+}
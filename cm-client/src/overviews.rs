@@ -0,0 +1,37 @@
+use anyhow::Result;
+use crackmes::{overview::OverviewCrackme, CrackmesClient};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// The overviews downloaded so far, by crackme ID, so the picker's preview and `get` share them
+/// instead of downloading the same page twice
+#[derive(Clone, Default, Debug)]
+pub struct OverviewCache(Arc<Mutex<HashMap<String, Arc<OverviewCrackme<'static>>>>>);
+
+impl OverviewCache {
+    fn get(&self, id: &str) -> Option<Arc<OverviewCrackme<'static>>> {
+        self.0.lock().unwrap().get(id).cloned()
+    }
+
+    /// The overview of `id`, which is only downloaded if it isn't cached yet
+    pub async fn fetch(
+        &self,
+        client: &CrackmesClient,
+        id: &str,
+    ) -> Result<Arc<OverviewCrackme<'static>>> {
+        if let Some(overview) = self.get(id) {
+            return Ok(overview);
+        }
+
+        // the lock isn't held while downloading, at worst two requests race and one is kept
+        let overview = Arc::new(client.overview(id).await?);
+        self.0
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), Arc::clone(&overview));
+
+        Ok(overview)
+    }
+}
@@ -0,0 +1,63 @@
+use crackmes::{list::ListCrackme, overview::OverviewCrackme, Html};
+use serde_json::{json, Value};
+use std::{env, fs, path::PathBuf, process};
+
+/// A crackme for tests, with the fields in `fields` replacing the defaults
+pub fn crackme(fields: Value) -> ListCrackme<'static> {
+    serde_json::from_value(with_defaults(fields)).unwrap()
+}
+
+/// An overview page for a crackme called `name` by `author`, parsed the same way as a real one
+pub fn overview(name: &str, author: &str) -> OverviewCrackme<'static> {
+    let page = format!(
+        r#"<html><body>
+            <h3><a href="/user/{author}">{author}</a>'s {name}</h3>
+            <div class="columns panel-background">
+                <div class="column col-3"><p>Author</p><p>{author}</p></div>
+                <div class="column col-3"><p>Language</p><p>C/C++</p></div>
+                <div class="column col-3"><p>Upload</p><p>5:40 PM 05/07/2021</p></div>
+                <div class="column col-3"><p>Platform</p><p>Windows</p></div>
+                <div class="column col-3"><p>Difficulty</p><p>2.0</p></div>
+                <div class="column col-3"><p>Quality</p><p>3.0</p></div>
+            </div>
+            <div class="columns"><div class="column col-12"><span>a crackme</span></div></div>
+        </body></html>"#,
+        name = name,
+        author = author
+    );
+
+    let html = Html::parse_document(&page);
+    OverviewCrackme::with_full_html(&html, "60957b9a33c5d458ce0ec88e")
+        .unwrap()
+        .into_owned()
+}
+
+fn with_defaults(fields: Value) -> Value {
+    let mut crackme = json!({
+        "name": "crackme",
+        "author": "someone",
+        "language": "COrCPlusPlus",
+        "date": "5:40 PM 05/07/2021",
+        "platform": "Windows",
+        "stats": { "quality": 3.0, "difficulty": 2.0 },
+        "id": "60957b9a33c5d458ce0ec88e",
+        "solutions": 0,
+        "comments": 0,
+        "description": null,
+    });
+
+    if let (Some(crackme), Value::Object(fields)) = (crackme.as_object_mut(), fields) {
+        crackme.extend(fields);
+    }
+
+    crackme
+}
+
+/// An empty directory for a test, named after it so tests running at once don't share one
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("cmc-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
@@ -0,0 +1,387 @@
+use anyhow::Result;
+use std::{fs, path::PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// how many searches are remembered
+const HISTORY_LENGTH: usize = 100;
+
+/// The search box of the picker
+#[derive(Default, Debug)]
+pub struct SearchText {
+    text: String,
+    // a byte index into `text`, always on a grapheme boundary
+    cursor: usize,
+    // oldest first
+    history: Vec<String>,
+    // the entry of `history` being shown, and what was typed before going back through it
+    recalled: Option<(usize, String)>,
+}
+
+impl SearchText {
+    pub fn with_history(history: Vec<String>) -> SearchText {
+        SearchText {
+            history,
+            ..Default::default()
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Deletes the grapheme before the cursor
+    pub fn backspace(&mut self) {
+        let start = self.previous_boundary();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Deletes the grapheme after the cursor
+    pub fn delete(&mut self) {
+        let end = self.next_boundary();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Deletes the word before the cursor, and the spaces after it
+    pub fn delete_word(&mut self) {
+        let before = self.text[..self.cursor].trim_end();
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    /// Swaps the text for the search before the one shown
+    pub fn history_previous(&mut self) {
+        let i = match &self.recalled {
+            _ if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+            Some((i, _)) => i.saturating_sub(1),
+        };
+
+        let typed = match self.recalled.take() {
+            Some((_, typed)) => typed,
+            None => self.text.clone(),
+        };
+
+        self.show(self.history[i].clone());
+        self.recalled = Some((i, typed));
+    }
+
+    /// Swaps the text for the search after the one shown, or what was typed after the last one
+    pub fn history_next(&mut self) {
+        match self.recalled.take() {
+            Some((i, typed)) if i + 1 < self.history.len() => {
+                self.show(self.history[i + 1].clone());
+                self.recalled = Some((i + 1, typed));
+            }
+            Some((_, typed)) => self.show(typed),
+            None => {}
+        }
+    }
+
+    /// The history with the current search added to it
+    pub fn into_history(mut self) -> Vec<String> {
+        let text = self.text.trim();
+
+        if !text.is_empty() && self.history.last().map(String::as_str) != Some(text) {
+            self.history.push(text.to_string());
+        }
+
+        let extra = self.history.len().saturating_sub(HISTORY_LENGTH);
+        self.history.drain(..extra);
+
+        self.history
+    }
+
+    /// The part of the text that fits in a box `width` wide (around the cursor), and how many
+    /// columns into it the cursor is
+    pub fn view(&self, width: usize) -> (&str, u16) {
+        // accounting for pipe characters at beginning and end, and cursor
+        let available = width.saturating_sub(3);
+
+        // drop as few graphemes from the start as we can for the cursor to fit
+        let start = self.text[..self.cursor]
+            .grapheme_indices(true)
+            .map(|(i, _)| i)
+            .find(|&i| self.text[i..self.cursor].width() <= available)
+            .unwrap_or(self.cursor);
+
+        // then take as much after it as still fits
+        let end = self.text[start..]
+            .grapheme_indices(true)
+            .map(|(i, grapheme)| start + i + grapheme.len())
+            .take_while(|&end| self.text[start..end].width() <= available)
+            .last()
+            .unwrap_or(start)
+            .max(self.cursor);
+
+        let cursor = self.text[start..self.cursor].width() as u16;
+        (&self.text[start..end], cursor)
+    }
+
+    fn show(&mut self, text: String) {
+        self.text = text;
+        self.cursor = self.text.len();
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map(|g| self.cursor + g.len())
+            .unwrap_or_else(|| self.text.len())
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("cmc").join("history"))
+}
+
+/// The searches from earlier runs, oldest first
+pub fn load_history() -> Result<Vec<String>> {
+    let path = match history_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(Vec::new()),
+    };
+
+    let history = fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+
+    Ok(history)
+}
+
+pub fn save_history(history: &[String]) -> Result<()> {
+    if let Some(path) = history_path() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut text = history.join("\n");
+        text.push('\n');
+        fs::write(path, text)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &str) -> SearchText {
+        let mut search = SearchText::default();
+        text.chars().for_each(|c| search.insert(c));
+        search
+    }
+
+    fn with_cursor(search: &SearchText) -> String {
+        let (before, after) = search.as_str().split_at(search.cursor);
+        format!("{}|{}", before, after)
+    }
+
+    #[test]
+    fn editing_multibyte() {
+        // "é" as one code point, then as "e" with a combining accent
+        let mut search = typed("día\u{e9}e\u{301}🦀");
+        assert_eq!(with_cursor(&search), "día\u{e9}e\u{301}🦀|");
+
+        search.left();
+        assert_eq!(with_cursor(&search), "día\u{e9}e\u{301}|🦀");
+        // the accent goes with its letter
+        search.left();
+        assert_eq!(with_cursor(&search), "día\u{e9}|e\u{301}🦀");
+
+        search.delete();
+        assert_eq!(with_cursor(&search), "día\u{e9}|🦀");
+        search.backspace();
+        assert_eq!(with_cursor(&search), "día|🦀");
+
+        search.insert('ñ');
+        assert_eq!(with_cursor(&search), "díañ|🦀");
+        search.right();
+        assert_eq!(with_cursor(&search), "díañ🦀|");
+
+        // nothing past either end
+        search.right();
+        search.delete();
+        assert_eq!(with_cursor(&search), "díañ🦀|");
+        search.home();
+        search.left();
+        search.backspace();
+        assert_eq!(with_cursor(&search), "|díañ🦀");
+
+        search.end();
+        search.backspace();
+        search.backspace();
+        assert_eq!(with_cursor(&search), "día|");
+
+        search.clear();
+        assert_eq!(with_cursor(&search), "|");
+    }
+
+    #[test]
+    fn combining_after_cursor_move() {
+        let mut search = typed("ax");
+        search.left();
+        search.insert('\u{301}');
+
+        assert_eq!(with_cursor(&search), "a\u{301}|x");
+        search.backspace();
+        assert_eq!(with_cursor(&search), "|x");
+    }
+
+    #[test]
+    fn delete_word_cases() {
+        // the text with the cursor ("|") before and after deleting a word
+        let cases = [
+            ("|foo bar", "|foo bar"),
+            ("foo bar|", "foo |"),
+            ("foo bar  |", "foo |"),
+            ("foo ba|r baz", "foo |r baz"),
+            ("foo bar| baz", "foo | baz"),
+            ("foo|", "|"),
+            ("  foo|", "  |"),
+            ("día añejo|", "día |"),
+        ];
+
+        for (before, after) in &cases {
+            let (text, rest) = before.split_at(before.find('|').unwrap());
+            let mut search = typed(&format!("{}{}", text, &rest[1..]));
+            search.cursor = text.len();
+
+            search.delete_word();
+            assert_eq!(with_cursor(&search), *after, "{:?}", before);
+        }
+    }
+
+    #[test]
+    fn view_cases() {
+        // each of these is two columns wide
+        let mut search = typed("漢字漢字");
+
+        // 7 columns leave 4 for the text
+        assert_eq!(search.view(7), ("漢字", 4));
+
+        search.home();
+        assert_eq!(search.view(7), ("漢字", 0));
+        search.right();
+        assert_eq!(search.view(7), ("漢字", 2));
+        search.right();
+        search.right();
+        assert_eq!(search.view(7), ("字漢", 4));
+
+        // an accent takes no room
+        let search = typed("e\u{301}x");
+        assert_eq!(search.view(10), ("e\u{301}x", 2));
+
+        // too narrow for anything, but the cursor still has a place
+        assert_eq!(typed("abc").view(2), ("", 0));
+    }
+
+    #[test]
+    fn history_keeps_draft() {
+        let mut search = SearchText::with_history(vec!["first".into(), "second".into()]);
+        "dra".chars().for_each(|c| search.insert(c));
+
+        search.history_previous();
+        assert_eq!(with_cursor(&search), "second|");
+        search.history_previous();
+        assert_eq!(search.as_str(), "first");
+        // stops at the oldest
+        search.history_previous();
+        assert_eq!(search.as_str(), "first");
+
+        search.history_next();
+        assert_eq!(search.as_str(), "second");
+        search.history_next();
+        assert_eq!(with_cursor(&search), "dra|");
+        // and at what was typed
+        search.history_next();
+        assert_eq!(search.as_str(), "dra");
+
+        // going back again still comes back to the draft
+        search.history_previous();
+        search.insert('!');
+        search.history_next();
+        assert_eq!(search.as_str(), "dra");
+    }
+
+    #[test]
+    fn history_without_entries() {
+        let mut search = typed("abc");
+        search.history_previous();
+        search.history_next();
+
+        assert_eq!(with_cursor(&search), "abc|");
+    }
+
+    #[test]
+    fn into_history_cases() {
+        let history = || vec!["a".to_string(), "b".to_string()];
+
+        assert_eq!(
+            SearchText::with_history(history()).into_history(),
+            history()
+        );
+
+        let mut search = SearchText::with_history(history());
+        search.history_previous();
+        assert_eq!(search.into_history(), history());
+
+        let mut search = SearchText::with_history(history());
+        " c ".chars().for_each(|c| search.insert(c));
+        assert_eq!(search.into_history(), vec!["a", "b", "c"]);
+
+        let long = (0..HISTORY_LENGTH).map(|i| i.to_string()).collect();
+        let mut search = SearchText::with_history(long);
+        search.insert('x');
+        let history = search.into_history();
+        assert_eq!(history.len(), HISTORY_LENGTH);
+        assert_eq!(history.first().map(String::as_str), Some("1"));
+        assert_eq!(history.last().map(String::as_str), Some("x"));
+    }
+}
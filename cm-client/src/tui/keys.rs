@@ -0,0 +1,575 @@
+use super::search::Column;
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{de::IntoDeserializer, Deserialize};
+use std::{collections::HashMap, fmt};
+
+/// Something a key in the picker can do
+#[derive(Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Confirm,
+    Cancel,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    Top,
+    Bottom,
+    Mark,
+    Help,
+    CursorLeft,
+    CursorRight,
+    CursorStart,
+    CursorEnd,
+    DeleteWord,
+    ClearSearch,
+    HistoryPrevious,
+    HistoryNext,
+    NextTab,
+    PreviousTab,
+    ScrollPreviewDown,
+    ScrollPreviewUp,
+    SortRelevance,
+    SortName,
+    SortAuthor,
+    SortLanguage,
+    SortPlatform,
+    SortDifficulty,
+    SortQuality,
+    SortDate,
+    SortSolutions,
+    SortComments,
+}
+
+impl Action {
+    /// The column this action sorts by, `None` for sorting by the search again
+    pub fn sort_column(self) -> Option<Option<Column>> {
+        let column = match self {
+            Action::SortRelevance => None,
+            Action::SortName => Some(Column::Name),
+            Action::SortAuthor => Some(Column::Author),
+            Action::SortLanguage => Some(Column::Language),
+            Action::SortPlatform => Some(Column::Platform),
+            Action::SortDifficulty => Some(Column::Difficulty),
+            Action::SortQuality => Some(Column::Quality),
+            Action::SortDate => Some(Column::Date),
+            Action::SortSolutions => Some(Column::Solutions),
+            Action::SortComments => Some(Column::Comments),
+            _ => return None,
+        };
+
+        Some(column)
+    }
+
+    /// Whether this changes the search box, these win over other actions on the same key while
+    /// there is something in it
+    fn edits_search(self) -> bool {
+        matches!(
+            self,
+            Action::CursorLeft
+                | Action::CursorRight
+                | Action::CursorStart
+                | Action::CursorEnd
+                | Action::DeleteWord
+                | Action::ClearSearch
+                | Action::HistoryPrevious
+                | Action::HistoryNext
+        )
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Action::Confirm => "get the marked (or selected) crackmes",
+            Action::Cancel => "quit without getting anything",
+            Action::Up => "move up",
+            Action::Down => "move down",
+            Action::PageUp => "move up a page",
+            Action::PageDown => "move down a page",
+            Action::HalfPageUp => "move up half a page",
+            Action::HalfPageDown => "move down half a page",
+            Action::Top => "go to the top",
+            Action::Bottom => "go to the bottom",
+            Action::Mark => "mark or unmark the selected crackme",
+            Action::Help => "show or hide this help",
+            Action::CursorLeft => "move the cursor left",
+            Action::CursorRight => "move the cursor right",
+            Action::CursorStart => "move the cursor to the start (while typing)",
+            Action::CursorEnd => "move the cursor to the end (while typing)",
+            Action::DeleteWord => "delete the word before the cursor",
+            Action::ClearSearch => "clear the search (while typing)",
+            Action::HistoryPrevious => "show the previous search (up while typing)",
+            Action::HistoryNext => "show the next search (down while typing)",
+            Action::NextTab => "show the next tab of the preview",
+            Action::PreviousTab => "show the previous tab of the preview",
+            Action::ScrollPreviewDown => "scroll the preview down",
+            Action::ScrollPreviewUp => "scroll the preview up",
+            Action::SortRelevance => "sort by the search",
+            Action::SortName => "sort by name",
+            Action::SortAuthor => "sort by author",
+            Action::SortLanguage => "sort by language",
+            Action::SortPlatform => "sort by platform",
+            Action::SortDifficulty => "sort by difficulty",
+            Action::SortQuality => "sort by quality",
+            Action::SortDate => "sort by date",
+            Action::SortSolutions => "sort by solutions",
+            Action::SortComments => "sort by comments",
+        }
+    }
+}
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Confirm, &["enter"]),
+    (Action::Cancel, &["esc", "ctrl-c"]),
+    (Action::Up, &["up", "ctrl-k"]),
+    (Action::Down, &["down", "ctrl-j"]),
+    (Action::PageUp, &["pageup"]),
+    (Action::PageDown, &["pagedown"]),
+    (Action::HalfPageUp, &["ctrl-u"]),
+    (Action::HalfPageDown, &["ctrl-d"]),
+    (Action::Top, &["g g", "home"]),
+    (Action::Bottom, &["G", "end"]),
+    (Action::Mark, &["tab", "space"]),
+    (Action::Help, &["?"]),
+    (Action::CursorLeft, &["left"]),
+    (Action::CursorRight, &["right"]),
+    (Action::CursorStart, &["home", "ctrl-a"]),
+    (Action::CursorEnd, &["end", "ctrl-e"]),
+    (Action::DeleteWord, &["ctrl-w"]),
+    (Action::ClearSearch, &["ctrl-u"]),
+    // up and down move through the list until something is typed, ctrl-k and ctrl-j always do
+    (Action::HistoryPrevious, &["up", "ctrl-p", "alt-up"]),
+    (Action::HistoryNext, &["down", "ctrl-n", "alt-down"]),
+    (Action::NextTab, &["alt-right", "]"]),
+    (Action::PreviousTab, &["alt-left", "["]),
+    (Action::ScrollPreviewDown, &["shift-down"]),
+    (Action::ScrollPreviewUp, &["shift-up"]),
+    (Action::SortRelevance, &["alt-r"]),
+    (Action::SortName, &["alt-n"]),
+    (Action::SortAuthor, &["alt-a"]),
+    (Action::SortLanguage, &["alt-l"]),
+    (Action::SortPlatform, &["alt-p"]),
+    (Action::SortDifficulty, &["alt-d"]),
+    (Action::SortQuality, &["alt-q"]),
+    (Action::SortDate, &["alt-t"]),
+    (Action::SortSolutions, &["alt-s"]),
+    (Action::SortComments, &["alt-c"]),
+];
+
+/// A single key press, ignoring shift on characters since the character already says it
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Key {
+        let modifiers = match event.code {
+            KeyCode::Char(_) => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+
+        Key {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl Key {
+    pub fn code(self) -> KeyCode {
+        self.code
+    }
+
+    /// The character this key types into the search, if any
+    pub fn text(self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+
+    // parses keys like "ctrl-j", "pageup", "G" or "space"
+    fn parse(s: &str) -> Result<Key> {
+        let mut modifiers = KeyModifiers::empty();
+        let mut rest = s;
+
+        // "-" on its own is a key too
+        while let Some((modifier, after)) = rest.split_once('-').filter(|(_, a)| !a.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow!("Invalid modifier in key: {}", s)),
+            };
+            rest = after;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f => f
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse().ok())
+                    .map(KeyCode::F)
+                    .ok_or_else(|| anyhow!("Invalid key: {}", s))?,
+            },
+        };
+
+        Ok(Key::from(KeyEvent::new(code, modifiers)))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+/// What a key press turned out to mean
+#[derive(PartialEq, Debug)]
+pub enum Input {
+    Action(Action),
+    /// The key starts a binding with more keys, so wait for the next one
+    Pending,
+    /// The keys aren't bound to anything
+    Unbound(Vec<Key>),
+}
+
+/// The key bindings of the picker
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+    // the keys pressed so far of a binding with several keys (like "g g")
+    pending: Vec<Key>,
+}
+
+impl Keymap {
+    /// The default bindings, with the actions in `custom` replaced by the keys given there
+    pub fn new(custom: &HashMap<String, Vec<String>>) -> Result<Keymap> {
+        // toml can't deserialize enums as keys, so the names are checked here
+        let custom = custom
+            .iter()
+            .map(|(name, keys)| {
+                Action::deserialize(name.as_str().into_deserializer())
+                    .map(|action| (action, keys))
+                    .map_err(|e: serde::de::value::Error| anyhow!("Invalid key action: {}", e))
+            })
+            .collect::<Result<HashMap<Action, &Vec<String>>>>()?;
+
+        let mut bindings = Vec::new();
+
+        for &(action, defaults) in DEFAULT_BINDINGS {
+            let keys: Vec<&str> = match custom.get(&action) {
+                Some(keys) => keys.iter().map(String::as_str).collect(),
+                None => defaults.to_vec(),
+            };
+
+            for sequence in keys {
+                let sequence = sequence
+                    .split_whitespace()
+                    .map(Key::parse)
+                    .collect::<Result<Vec<_>>>()?;
+
+                if !sequence.is_empty() {
+                    bindings.push((sequence, action));
+                }
+            }
+        }
+
+        for (keys, action) in &bindings {
+            // the shorter binding always goes off first, so the longer one could never be used,
+            // and of two on the same keys only one goes off, unless one is just for typing
+            let hidden = bindings.iter().find(|(other, other_action)| {
+                let hides = other.len() > keys.len() && other.starts_with(keys);
+                let same = other == keys
+                    && other_action != action
+                    && other_action.edits_search() == action.edits_search();
+
+                hides || same
+            });
+
+            if let Some((other, other_action)) = hidden {
+                return Err(anyhow!(
+                    "The keys \"{}\" ({}) stop \"{}\" ({}) from ever being used",
+                    sequence_name(keys),
+                    action.description(),
+                    sequence_name(other),
+                    other_action.description()
+                ));
+            }
+        }
+
+        Ok(Keymap {
+            bindings,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Works out what a key press means, `typing` is set once there is text in the search (then
+    /// keys that type something are typed instead of used for bindings)
+    pub fn feed(&mut self, key: Key, typing: bool) -> Input {
+        if typing && self.pending.is_empty() && key.text().is_some() {
+            return Input::Unbound(vec![key]);
+        }
+
+        self.pending.push(key);
+
+        let bound: Vec<Action> = self
+            .bindings
+            .iter()
+            .filter(|(keys, _)| keys == &self.pending)
+            .map(|&(_, action)| action)
+            .collect();
+
+        // keys like home are for the search while typing and for the list otherwise
+        let action = bound
+            .iter()
+            .find(|action| action.edits_search() == typing)
+            .or_else(|| bound.first());
+
+        if let Some(&action) = action {
+            self.pending.clear();
+            return Input::Action(action);
+        }
+
+        if self
+            .bindings
+            .iter()
+            .any(|(keys, _)| keys.starts_with(&self.pending))
+        {
+            return Input::Pending;
+        }
+
+        Input::Unbound(std::mem::take(&mut self.pending))
+    }
+
+    /// A line for each action and the keys that do it
+    pub fn help(&self) -> Vec<String> {
+        DEFAULT_BINDINGS
+            .iter()
+            .map(|&(action, _)| {
+                let keys: Vec<String> = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, a)| *a == action)
+                    .map(|(keys, _)| sequence_name(keys))
+                    .collect();
+
+                format!("{:<16} {}", keys.join(", "), action.description())
+            })
+            .collect()
+    }
+}
+
+// how a binding is written in the config, e.g. "g g"
+fn sequence_name(keys: &[Key]) -> String {
+    keys.iter()
+        .map(Key::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        Key::from(KeyEvent::new(code, modifiers))
+    }
+
+    fn char(c: char) -> Key {
+        key(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn keymap(custom: &[(&str, &[&str])]) -> Result<Keymap> {
+        let custom = custom
+            .iter()
+            .map(|(action, keys)| {
+                let keys = keys.iter().map(|k| k.to_string()).collect();
+                (action.to_string(), keys)
+            })
+            .collect();
+
+        Keymap::new(&custom)
+    }
+
+    #[test]
+    fn parse_cases() {
+        let cases = [
+            ("ctrl-j", key(KeyCode::Char('j'), KeyModifiers::CONTROL)),
+            (
+                "CTRL-ALT-x",
+                key(
+                    KeyCode::Char('x'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT,
+                ),
+            ),
+            ("G", char('G')),
+            // shift is already in the character
+            ("shift-a", char('a')),
+            ("shift-up", key(KeyCode::Up, KeyModifiers::SHIFT)),
+            ("-", char('-')),
+            ("ctrl--", key(KeyCode::Char('-'), KeyModifiers::CONTROL)),
+            ("space", char(' ')),
+            ("f", char('f')),
+            ("F5", key(KeyCode::F(5), KeyModifiers::NONE)),
+            ("PageUp", key(KeyCode::PageUp, KeyModifiers::NONE)),
+            ("backtab", key(KeyCode::BackTab, KeyModifiers::NONE)),
+        ];
+
+        for (text, parsed) in &cases {
+            assert_eq!(Key::parse(text).unwrap(), *parsed, "{:?}", text);
+        }
+
+        for text in &["", "hyper-x", "ctrl-", "ctrl-nope", "fx", "f-1", "enterr"] {
+            assert!(Key::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for text in &[
+            "ctrl-j",
+            "alt-shift-down",
+            "space",
+            "f12",
+            "pagedown",
+            "G",
+            "-",
+        ] {
+            let key = Key::parse(text).unwrap();
+            assert_eq!(key.to_string(), *text);
+            assert_eq!(Key::parse(&key.to_string()).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn sequences() {
+        let mut keymap = keymap(&[]).unwrap();
+
+        assert_eq!(keymap.feed(char('g'), false), Input::Pending);
+        assert_eq!(keymap.feed(char('g'), false), Input::Action(Action::Top));
+
+        // a sequence that goes nowhere gives back every key of it
+        assert_eq!(keymap.feed(char('g'), false), Input::Pending);
+        assert_eq!(
+            keymap.feed(char('x'), false),
+            Input::Unbound(vec![char('g'), char('x')])
+        );
+
+        // while typing, characters are typed instead
+        assert_eq!(
+            keymap.feed(char('g'), true),
+            Input::Unbound(vec![char('g')])
+        );
+        assert_eq!(
+            keymap.feed(char('G'), true),
+            Input::Unbound(vec![char('G')])
+        );
+        assert_eq!(keymap.feed(char('G'), false), Input::Action(Action::Bottom));
+    }
+
+    #[test]
+    fn shared_keys_depend_on_typing() {
+        let mut keymap = keymap(&[]).unwrap();
+        let home = key(KeyCode::Home, KeyModifiers::NONE);
+        let ctrl_u = key(KeyCode::Char('u'), KeyModifiers::CONTROL);
+
+        assert_eq!(keymap.feed(home, false), Input::Action(Action::Top));
+        assert_eq!(keymap.feed(home, true), Input::Action(Action::CursorStart));
+        assert_eq!(
+            keymap.feed(ctrl_u, false),
+            Input::Action(Action::HalfPageUp)
+        );
+        assert_eq!(
+            keymap.feed(ctrl_u, true),
+            Input::Action(Action::ClearSearch)
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let mut keymap =
+            keymap(&[("top", &["t"]), ("help", &[]), ("mark", &["ctrl-x ctrl-m"])]).unwrap();
+
+        // only the overridden actions lose their default keys
+        assert_eq!(keymap.feed(char('t'), false), Input::Action(Action::Top));
+        assert_eq!(
+            keymap.feed(char('g'), false),
+            Input::Unbound(vec![char('g')])
+        );
+        assert_eq!(
+            keymap.feed(char('?'), false),
+            Input::Unbound(vec![char('?')])
+        );
+        assert_eq!(keymap.feed(char('G'), false), Input::Action(Action::Bottom));
+
+        let ctrl_x = key(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        let ctrl_m = key(KeyCode::Char('m'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.feed(ctrl_x, false), Input::Pending);
+        assert_eq!(keymap.feed(ctrl_m, false), Input::Action(Action::Mark));
+
+        assert!(keymap
+            .help()
+            .iter()
+            .any(|line| line.starts_with("ctrl-x ctrl-m ")));
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        // unknown actions and keys
+        assert!(keymap(&[("jump", &["j"])]).is_err());
+        assert!(keymap(&[("top", &["g hyper-g"])]).is_err());
+
+        // a binding that starts another one
+        assert!(keymap(&[("mark", &["g"])]).is_err());
+        assert!(keymap(&[("mark", &["g g x"])]).is_err());
+        assert!(keymap(&[("mark", &["g x"])]).is_ok());
+
+        // two actions on the same keys, which is only fine if one is for the search
+        assert!(keymap(&[("mark", &["enter"])]).is_err());
+        assert!(keymap(&[("mark", &["home"])]).is_err());
+        assert!(keymap(&[("mark", &["tab", "tab"])]).is_ok());
+        assert!(keymap(&[("mark", &["ctrl-w"]), ("delete-word", &["alt-backspace"])]).is_ok());
+        assert!(keymap(&[("delete-word", &["left"])]).is_err());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod input;
+pub mod keys;
+pub mod prefetch;
+pub mod query;
 pub mod term;
 pub mod search;
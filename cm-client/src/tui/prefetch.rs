@@ -0,0 +1,100 @@
+use crate::overviews::OverviewCache;
+use anyhow::Result;
+use crackmes::{overview::OverviewCrackme, CrackmesClient};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+
+// how many overviews are downloaded at once
+const JOBS: usize = 3;
+
+type Fetched = (usize, Result<Arc<OverviewCrackme<'static>>>);
+
+/// Downloads crackme overviews (for the description, comments and solutions) in the background,
+/// so the picker never waits on them
+pub struct Prefetcher {
+    requests: UnboundedSender<Vec<(usize, String)>>,
+    results: UnboundedReceiver<Fetched>,
+    task: JoinHandle<()>,
+}
+
+impl Prefetcher {
+    /// Overviews in `overviews` come straight from there, and new ones are added to it
+    pub fn new(client: CrackmesClient, overviews: OverviewCache) -> Prefetcher {
+        let (requests, mut requested) = mpsc::unbounded_channel::<Vec<(usize, String)>>();
+        let (sender, results) = mpsc::unbounded_channel();
+
+        let task = tokio::spawn(async move {
+            // what is still to be fetched, in order, and the IDs being fetched right now
+            let mut queue = VecDeque::new();
+            let mut fetching = HashSet::new();
+            let mut in_flight = FuturesUnordered::new();
+
+            loop {
+                tokio::select! {
+                    wanted = requested.recv() => match wanted {
+                        // only the latest request matters, the rest are for rows that were left
+                        Some(mut wanted) => {
+                            while let Ok(newer) = requested.try_recv() {
+                                wanted = newer;
+                            }
+                            queue = wanted.into_iter().collect();
+                        }
+                        None => break,
+                    },
+                    Some((id, result)) = in_flight.next(), if !in_flight.is_empty() => {
+                        fetching.remove(&id);
+                        // the picker has closed if nobody is listening, so there is nothing to do
+                        let _ = sender.send(result);
+                    }
+                }
+
+                while in_flight.len() < JOBS {
+                    let (i, id) = match queue.pop_front() {
+                        Some(request) => request,
+                        None => break,
+                    };
+
+                    if fetching.insert(id.clone()) {
+                        let client = client.clone();
+                        let overviews = overviews.clone();
+                        in_flight.push(async move {
+                            let result = overviews.fetch(&client, &id).await;
+                            (id, (i, result))
+                        });
+                    }
+                }
+            }
+        });
+
+        Prefetcher {
+            requests,
+            results,
+            task,
+        }
+    }
+
+    /// Fetches the overviews for these `(index, id)` pairs, in order, instead of the ones from
+    /// earlier requests that haven't been started yet
+    pub fn request(&self, wanted: Vec<(usize, String)>) {
+        // the task only stops when we drop it
+        let _ = self.requests.send(wanted);
+    }
+
+    /// The next overview to arrive, with the index it was requested with
+    pub async fn next(&mut self) -> Option<Fetched> {
+        self.results.recv().await
+    }
+}
+
+impl Drop for Prefetcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,374 @@
+use crackmes::list::ListCrackme;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use std::{fmt::Display, str::FromStr};
+
+// how much a match in each field is worth, so the name and author win over the rest
+const NAME_WEIGHT: i64 = 4;
+const AUTHOR_WEIGHT: i64 = 3;
+const DESCRIPTION_WEIGHT: i64 = 1;
+const OTHER_WEIGHT: i64 = 1;
+
+/// How well a crackme matched, and which characters of its name and author did
+#[derive(Debug, Default, Clone)]
+pub struct Match {
+    pub score: i64,
+    pub name: Vec<usize>,
+    pub author: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    Name(String),
+    Author(String),
+    Language(String),
+    Platform(String),
+    Difficulty(Comparison, f32),
+    Quality(Comparison, f32),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    // the rating rounds to the number
+    Equal,
+}
+
+impl Comparison {
+    fn holds(self, value: f32, wanted: f32) -> bool {
+        match self {
+            Comparison::Less => value < wanted,
+            Comparison::LessOrEqual => value <= wanted,
+            Comparison::Greater => value > wanted,
+            Comparison::GreaterOrEqual => value >= wanted,
+            Comparison::Equal => (value.round() - wanted).abs() < f32::EPSILON,
+        }
+    }
+}
+
+/// What was typed into the picker, e.g. "keygen author:DirkD lang:cpp diff:>3"
+#[derive(Debug, Default, PartialEq)]
+pub struct Query {
+    // matched against every field
+    terms: Vec<String>,
+    filters: Vec<Filter>,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Query {
+        let mut query = Query::default();
+
+        for word in text.split_whitespace() {
+            match parse_filter(word) {
+                Some(filter) => query.filters.push(filter),
+                // anything that doesn't look like a filter is searched for as it is
+                None => query.terms.push(word.to_string()),
+            }
+        }
+
+        query
+    }
+
+    /// `None` if the crackme doesn't match every term and filter
+    pub fn matches(&self, matcher: &SkimMatcherV2, crackme: &ListCrackme<'_>) -> Option<Match> {
+        let mut found = Match::default();
+
+        for filter in &self.filters {
+            match filter {
+                Filter::Name(name) => {
+                    let (score, indices) = matcher.fuzzy_indices(crackme.name(), name)?;
+                    found.score += score * NAME_WEIGHT;
+                    found.name.extend(indices);
+                }
+                Filter::Author(author) => {
+                    let (score, indices) = matcher.fuzzy_indices(crackme.author(), author)?;
+                    found.score += score * AUTHOR_WEIGHT;
+                    found.author.extend(indices);
+                }
+                Filter::Language(language) => {
+                    found.score += enum_score(matcher, crackme.language(), language)?;
+                }
+                Filter::Platform(platform) => {
+                    found.score += enum_score(matcher, crackme.platform(), platform)?;
+                }
+                Filter::Difficulty(comparison, wanted) => {
+                    if !comparison.holds(crackme.stats().difficulty, *wanted) {
+                        return None;
+                    }
+                }
+                Filter::Quality(comparison, wanted) => {
+                    if !comparison.holds(crackme.stats().quality, *wanted) {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        let language = crackme.language().to_string();
+        let platform = crackme.platform().to_string();
+
+        // each term only counts in the field it matches best, so it can't span fields
+        for term in &self.terms {
+            let name = matcher.fuzzy_indices(crackme.name(), term);
+            let author = matcher.fuzzy_indices(crackme.author(), term);
+
+            let best = [
+                name.as_ref().map(|(s, _)| s * NAME_WEIGHT),
+                author.as_ref().map(|(s, _)| s * AUTHOR_WEIGHT),
+                crackme
+                    .description()
+                    .and_then(|d| matcher.fuzzy_match(d, term))
+                    .map(|s| s * DESCRIPTION_WEIGHT),
+                matcher
+                    .fuzzy_match(&language, term)
+                    .map(|s| s * OTHER_WEIGHT),
+                matcher
+                    .fuzzy_match(&platform, term)
+                    .map(|s| s * OTHER_WEIGHT),
+            ];
+
+            let (field, score) = best
+                .iter()
+                .enumerate()
+                .filter_map(|(field, score)| score.map(|s| (field, s)))
+                .max_by_key(|&(_, score)| score)?;
+
+            found.score += score;
+            match field {
+                0 => found.name.extend(name.map(|(_, i)| i).unwrap_or_default()),
+                1 => found
+                    .author
+                    .extend(author.map(|(_, i)| i).unwrap_or_default()),
+                _ => {}
+            }
+        }
+
+        found.name.sort_unstable();
+        found.name.dedup();
+        found.author.sort_unstable();
+        found.author.dedup();
+
+        Some(found)
+    }
+}
+
+// a filter looks like "field:value", and unknown fields aren't filters at all
+fn parse_filter(word: &str) -> Option<Filter> {
+    let (field, value) = word.split_at(word.find(':')?);
+    let value = &value[1..];
+
+    if value.is_empty() {
+        return None;
+    }
+
+    let filter = match field.to_lowercase().as_str() {
+        "name" => Filter::Name(value.to_string()),
+        "author" => Filter::Author(value.to_string()),
+        "lang" | "language" => Filter::Language(value.to_string()),
+        "plat" | "platform" => Filter::Platform(value.to_string()),
+        "diff" | "difficulty" => {
+            let (comparison, wanted) = parse_comparison(value)?;
+            Filter::Difficulty(comparison, wanted)
+        }
+        "qual" | "quality" => {
+            let (comparison, wanted) = parse_comparison(value)?;
+            Filter::Quality(comparison, wanted)
+        }
+        _ => return None,
+    };
+
+    Some(filter)
+}
+
+fn parse_comparison(value: &str) -> Option<(Comparison, f32)> {
+    let (comparison, number) = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ]
+    .iter()
+    .find_map(|(prefix, comparison)| value.strip_prefix(prefix).map(|n| (*comparison, n)))
+    .unwrap_or((Comparison::Equal, value));
+
+    number.parse().ok().map(|number| (comparison, number))
+}
+
+// the names we parse languages and platforms from (like "cpp") match exactly, anything else is
+// fuzzy matched against how they're shown
+fn enum_score<T>(matcher: &SkimMatcherV2, value: &T, wanted: &str) -> Option<i64>
+where
+    T: FromStr + PartialEq + Display,
+{
+    match wanted.parse::<T>() {
+        Ok(parsed) if &parsed == value => Some(0),
+        Ok(_) => None,
+        Err(_) => matcher
+            .fuzzy_match(&value.to_string(), wanted)
+            .map(|s| s * OTHER_WEIGHT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::crackme;
+    use serde_json::json;
+
+    fn terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_cases() {
+        let cases = vec![
+            ("", Query::default()),
+            (
+                "keygen  easy",
+                Query {
+                    terms: terms(&["keygen", "easy"]),
+                    filters: vec![],
+                },
+            ),
+            (
+                "name:safe AUTHOR:oles",
+                Query {
+                    terms: vec![],
+                    filters: vec![
+                        Filter::Name("safe".to_string()),
+                        Filter::Author("oles".to_string()),
+                    ],
+                },
+            ),
+            (
+                "lang:cpp language:vb plat:linux platform:windows",
+                Query {
+                    terms: vec![],
+                    filters: vec![
+                        Filter::Language("cpp".to_string()),
+                        Filter::Language("vb".to_string()),
+                        Filter::Platform("linux".to_string()),
+                        Filter::Platform("windows".to_string()),
+                    ],
+                },
+            ),
+            (
+                "diff:>3 difficulty:<=2.5 qual:4 quality:=1",
+                Query {
+                    terms: vec![],
+                    filters: vec![
+                        Filter::Difficulty(Comparison::Greater, 3.0),
+                        Filter::Difficulty(Comparison::LessOrEqual, 2.5),
+                        Filter::Quality(Comparison::Equal, 4.0),
+                        Filter::Quality(Comparison::Equal, 1.0),
+                    ],
+                },
+            ),
+            // unknown fields, empty values and numbers that don't parse are just searched for
+            (
+                "size:big name: diff:hard diff:>",
+                Query {
+                    terms: terms(&["size:big", "name:", "diff:hard", "diff:>"]),
+                    filters: vec![],
+                },
+            ),
+            // only the first ":" separates the field
+            (
+                "name:a:b",
+                Query {
+                    terms: vec![],
+                    filters: vec![Filter::Name("a:b".to_string())],
+                },
+            ),
+        ];
+
+        for (text, query) in cases {
+            assert_eq!(Query::parse(text), query, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn comparison_cases() {
+        let cases = [
+            (">=", 3.0, true),
+            (">=", 3.5, false),
+            ("<=", 3.0, true),
+            (">", 2.9, true),
+            (">", 3.0, false),
+            ("<", 3.1, true),
+            ("<", 3.0, false),
+            ("=", 3.0, true),
+            ("", 3.0, true),
+            ("", 4.0, false),
+        ];
+
+        for &(prefix, wanted, holds) in &cases {
+            let (comparison, number) = parse_comparison(&format!("{}{}", prefix, wanted)).unwrap();
+            assert_eq!(number, wanted);
+            assert_eq!(comparison.holds(3.0, wanted), holds, "{}{}", prefix, wanted);
+        }
+
+        // "=" is about the rounded rating
+        assert!(Comparison::Equal.holds(3.4, 3.0));
+        assert!(!Comparison::Equal.holds(3.5, 3.0));
+    }
+
+    #[test]
+    fn filter_cases() {
+        let matcher = SkimMatcherV2::default();
+        let crackme = crackme(json!({
+            "name": "SAFE_01",
+            "author": "oles",
+            "language": "VisualBasic",
+            "platform": "Windows",
+            "stats": { "quality": 3.7, "difficulty": 1.0 },
+        }));
+
+        let cases = [
+            ("", true),
+            ("name:safe", true),
+            ("name:keygen", false),
+            ("author:ole", true),
+            ("author:safe", false),
+            ("lang:vb", true),
+            ("lang:cpp", false),
+            ("plat:windows", true),
+            ("plat:linux", false),
+            ("plat:wind", true),
+            ("diff:1", true),
+            ("diff:>1", false),
+            ("qual:>=3.5 diff:<2", true),
+            ("qual:4 diff:1", true),
+            ("qual:3", false),
+            ("safe oles", true),
+            ("safe zzz", false),
+        ];
+
+        for &(text, matches) in &cases {
+            let found = Query::parse(text).matches(&matcher, &crackme);
+            assert_eq!(found.is_some(), matches, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn weights_rank_fields() {
+        let matcher = SkimMatcherV2::default();
+        let by_name = crackme(json!({ "name": "keygen", "author": "someone" }));
+        let by_author = crackme(json!({ "name": "crackme", "author": "keygen" }));
+        let by_description = crackme(json!({ "name": "crackme", "description": "a keygen" }));
+
+        let query = Query::parse("keygen");
+        let score = |crackme| query.matches(&matcher, crackme).unwrap().score;
+
+        assert!(score(&by_name) > score(&by_author));
+        assert!(score(&by_author) > score(&by_description));
+
+        // a term only highlights the field it matched best in
+        let found = query.matches(&matcher, &by_name).unwrap();
+        assert_eq!(found.name, (0..6).collect::<Vec<_>>());
+        assert!(found.author.is_empty());
+    }
+}
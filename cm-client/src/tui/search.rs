@@ -1,49 +1,168 @@
+use super::query::{Match, Query};
 use anyhow::Result;
-use crackmes::list::ListCrackme;
-use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use tui::widgets::{Block, Borders, List, ListItem, ListState};
+use chrono::NaiveDateTime;
+use crackmes::{list::ListCrackme, overview::OverviewCrackme};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+use tui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
 
-#[derive(Default, Debug)]
-pub struct SearchText(String);
+// how many crackmes either side of the selected one get their overviews fetched
+const PREFETCH_RADIUS: usize = 3;
 
-impl SearchText {
-    pub fn as_str(&self) -> &str {
-        &self.0
-    }
+// how the site writes dates, e.g. "5:40 PM 05/07/2021"
+const DATE_FORMAT: &str = "%I:%M %p %m/%d/%Y";
+
+// the marker and then every column
+const WIDTHS: [Constraint; 10] = [
+    Constraint::Length(3),
+    Constraint::Min(10),
+    Constraint::Length(12),
+    Constraint::Length(8),
+    Constraint::Length(10),
+    Constraint::Length(6),
+    Constraint::Length(6),
+    Constraint::Length(10),
+    Constraint::Length(5),
+    Constraint::Length(5),
+];
+
+/// The columns of the picker's table, which it can be sorted by
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Column {
+    Name,
+    Author,
+    Language,
+    Platform,
+    Difficulty,
+    Quality,
+    Date,
+    Solutions,
+    Comments,
+}
+
+impl Column {
+    const ALL: [Column; 9] = [
+        Column::Name,
+        Column::Author,
+        Column::Language,
+        Column::Platform,
+        Column::Difficulty,
+        Column::Quality,
+        Column::Date,
+        Column::Solutions,
+        Column::Comments,
+    ];
 
-    pub fn push(&mut self, c: char) {
-        self.0.push(c);
+    fn title(self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Author => "Author",
+            Column::Language => "Lang",
+            Column::Platform => "Platform",
+            Column::Difficulty => "Diff",
+            Column::Quality => "Qual",
+            Column::Date => "Date",
+            Column::Solutions => "Sol",
+            Column::Comments => "Com",
+        }
     }
 
-    pub fn pop(&mut self) {
-        self.0.pop();
+    fn compare(self, a: &ListCrackme<'_>, b: &ListCrackme<'_>) -> Ordering {
+        let date = |c: &ListCrackme<'_>| NaiveDateTime::parse_from_str(c.date(), DATE_FORMAT).ok();
+
+        match self {
+            Column::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+            Column::Author => a.author().to_lowercase().cmp(&b.author().to_lowercase()),
+            Column::Language => a.language().to_string().cmp(&b.language().to_string()),
+            Column::Platform => a.platform().to_string().cmp(&b.platform().to_string()),
+            Column::Difficulty => a
+                .stats()
+                .difficulty
+                .partial_cmp(&b.stats().difficulty)
+                .unwrap_or(Ordering::Equal),
+            Column::Quality => a
+                .stats()
+                .quality
+                .partial_cmp(&b.stats().quality)
+                .unwrap_or(Ordering::Equal),
+            Column::Date => date(a).cmp(&date(b)),
+            Column::Solutions => a.solutions().cmp(&b.solutions()),
+            Column::Comments => a.comments().cmp(&b.comments()),
+        }
     }
+}
 
-    pub fn get(&self, length: usize) -> &str {
-        // accounting for pipe characters at beginning and end, and cursor
-        let length = length.checked_sub(3).unwrap_or(length);
+/// The tabs of the preview next to the table
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum PreviewTab {
+    #[default]
+    Details,
+    Comments,
+    Solutions,
+}
 
-        let start = if self.0.len() > length {
-            self.0.len() - length
-        } else {
-            0
-        };
-        &self.0[start..]
+impl PreviewTab {
+    pub const ALL: [PreviewTab; 3] = [
+        PreviewTab::Details,
+        PreviewTab::Comments,
+        PreviewTab::Solutions,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            PreviewTab::Details => "Details",
+            PreviewTab::Comments => "Comments",
+            PreviewTab::Solutions => "Solutions",
+        }
+    }
+
+    fn index(self) -> usize {
+        PreviewTab::ALL.iter().position(|&t| t == self).unwrap_or(0)
     }
 }
 
 #[derive(Default)]
 pub struct Searcher<'crackme> {
-    store: &'crackme mut [ListCrackme<'crackme>],
+    store: &'crackme mut [ListCrackme<'static>],
+    // the results of the search, best match last
+    ranked: Vec<usize>,
+    // `ranked` in the order they're shown
     found: Vec<usize>,
-    state: ListState,
+    // the column the table is sorted by (instead of by the search), and if it is descending
+    sort: Option<(Column, bool)>,
+    // how each found crackme matched the search, by index into `store`
+    matches: HashMap<usize, Match>,
+    // indices into `store`, so they stay marked when the search changes
+    marked: BTreeSet<usize>,
+    // the overviews that came back, and why the ones that didn't failed, by index into `store`
+    overviews: HashMap<usize, Arc<OverviewCrackme<'static>>>,
+    failed: HashMap<usize, String>,
+    tab: PreviewTab,
+    state: TableState,
+    // where the table and preview were, as of the last time they were drawn
+    table_area: Rect,
+    preview_area: Rect,
+    // the first row the table shows, which the table widget keeps to itself, so this mirrors it
+    offset: usize,
+    // how far the preview is scrolled, and the crackme (index into `store`) it was scrolled for
+    preview_scroll: (u16, Option<usize>),
+    show_help: bool,
     matcher: SkimMatcherV2,
 }
 
-use reqwest::Client;
 impl<'a> Searcher<'a> {
-    pub fn new(store: &'a mut [ListCrackme<'a>]) -> Searcher<'a> {
+    pub fn new(store: &'a mut [ListCrackme<'static>]) -> Searcher<'a> {
         let mut searcher = Searcher {
+            ranked: (0..store.len()).collect(),
             found: (0..store.len()).collect(),
             store,
             ..Default::default()
@@ -53,97 +172,351 @@ impl<'a> Searcher<'a> {
         searcher
     }
 
-    pub async fn fetch_descriptions(&mut self, client: &mut Client) -> Result<()> {
-        // we start at the end of the vector, so we only have to download the current and next
-        // crackme's description (technically only current but we fetch the next one to lighten the
-        // load later), unless we skip to the top (through the gg bind), then we also need to
-        // download below us
-        if let Some(nearby) = self.found.len().checked_sub(1).and_then(|last| {
-            self.state.selected().map(|i| {
-                let start = i.saturating_sub(1);
+    /// The `(index, id)` of the crackmes around the selected one that still need an overview,
+    /// closest first (ones that failed aren't tried again)
+    pub fn wanted_overviews(&self) -> Vec<(usize, String)> {
+        // the selected one goes first so it shows up first
+        let nearby = match self.state.selected() {
+            Some(i) => (0..=PREFETCH_RADIUS)
+                .flat_map(|d| vec![i.checked_add(d), i.checked_sub(d)])
+                .flatten()
+                .flat_map(|i| self.found.get(i).copied())
+                .collect(),
+            None => Vec::new(),
+        };
 
-                // "saturating" add on the len
-                let end = if i + 1 > last { last } else { i + 1 };
+        let mut wanted: Vec<(usize, String)> = Vec::new();
+        for i in nearby {
+            let missing = !self.overviews.contains_key(&i) && !self.failed.contains_key(&i);
+            if missing && wanted.iter().all(|&(w, _)| w != i) {
+                wanted.push((i, self.store[i].id().to_string()));
+            }
+        }
 
-                start..=end
-            })
-        }) {
-            for i in nearby {
-                let crackme = &mut self.store[i];
-                if crackme.description().is_none() {
-                    crackme
-                        .try_set_description(
-                            crate::get::get_description(client, crackme.id()).await?,
-                        )
-                        .unwrap();
+        wanted
+    }
+
+    /// Stores an overview that was fetched, or why it couldn't be
+    pub fn set_overview(&mut self, i: usize, overview: Result<Arc<OverviewCrackme<'static>>>) {
+        if let Some(crackme) = self.store.get_mut(i) {
+            match overview {
+                Ok(overview) => {
+                    // so the search can match it too
+                    let _ = crackme.try_set_description(overview.description().to_string());
+                    self.overviews.insert(i, overview);
                 }
+                Err(e) => {
+                    self.failed.insert(i, format!("{:#}", e));
+                }
+            }
+        }
+    }
+
+    /// What to show in the current tab for the selected crackme
+    pub fn preview(&self) -> String {
+        let crackme = match self.selected() {
+            Some(crackme) => crackme,
+            None => return String::new(),
+        };
+        let overview = self.selected_index().and_then(|i| self.overviews.get(&i));
+        let failed = self.selected_index().and_then(|i| self.failed.get(&i));
+
+        // the selected crackme's overview is always the first one fetched
+        let status = match failed {
+            Some(e) => format!("Couldn't load: {}", e),
+            None => "loading…".to_string(),
+        };
+
+        match (self.tab, overview) {
+            (PreviewTab::Details, None) if crackme.description().is_none() => {
+                let mut loading = crackme.clone();
+                let _ = loading.try_set_description(status);
+                loading.to_string()
+            }
+            (PreviewTab::Details, _) => crackme.to_string(),
+            (_, None) => status,
+            (PreviewTab::Comments, Some(overview)) if overview.comments().is_empty() => {
+                "No comments yet".to_string()
+            }
+            (PreviewTab::Comments, Some(overview)) => join(overview.comments()),
+            (PreviewTab::Solutions, Some(overview)) if overview.solutions().is_empty() => {
+                "No solutions yet".to_string()
             }
+            (PreviewTab::Solutions, Some(overview)) => join(overview.solutions()),
         }
-        Ok(())
     }
 
-    pub fn state(&mut self) -> &mut ListState {
+    /// The titles of the preview's tabs, with how many comments and solutions there are
+    pub fn tab_titles(&self) -> Vec<String> {
+        let overview = self.selected_index().and_then(|i| self.overviews.get(&i));
+
+        PreviewTab::ALL
+            .iter()
+            .map(|&tab| match (tab, overview) {
+                (PreviewTab::Comments, Some(o)) => {
+                    format!("{} ({})", tab.title(), o.comments().len())
+                }
+                (PreviewTab::Solutions, Some(o)) => {
+                    format!("{} ({})", tab.title(), o.solutions().len())
+                }
+                _ => tab.title().to_string(),
+            })
+            .collect()
+    }
+
+    /// The index of the tab being shown
+    pub fn tab(&self) -> usize {
+        self.tab.index()
+    }
+
+    /// Shows the tab `by` tabs after the current one (before if negative), wrapping around
+    pub fn switch_tab(&mut self, by: isize) {
+        let count = PreviewTab::ALL.len() as isize;
+        let i = (self.tab.index() as isize + by).rem_euclid(count);
+
+        self.tab = PreviewTab::ALL[i as usize];
+        self.preview_scroll = (0, None);
+    }
+
+    pub fn state(&mut self) -> &mut TableState {
         &mut self.state
     }
 
     pub fn search(&mut self, query: &str) {
-        let items = self
+        let query = Query::parse(query);
+
+        let mut matches: Vec<(usize, Match)> = self
             .store
             .iter()
             .enumerate()
-            .filter(|(_, crackme)| {
-                self.matcher
-                    .fuzzy_match(&crackme.to_search_string(), query.trim())
-                    .is_some()
+            .filter_map(|(index, crackme)| {
+                query
+                    .matches(&self.matcher, crackme)
+                    .map(|found| (index, found))
             })
-            .map(|(index, _)| index)
             .collect();
 
-        self.found = items;
+        // the best match ends up at the bottom, next to the search box, where the selection
+        // starts (this is stable, so an empty search keeps the original order)
+        matches.sort_by_key(|(_, found)| found.score);
+
+        self.ranked = matches.iter().map(|&(index, _)| index).collect();
+        self.matches = matches.into_iter().collect();
+        self.found = self.ranked.clone();
+        self.apply_sort();
         self.last();
     }
 
-    pub fn list(&self) -> List<'static> {
-        let items: Vec<ListItem> = self
+    /// Sorts by the column (flipping the order if it already was), or by the search if `None`
+    pub fn sort(&mut self, column: Option<Column>) {
+        self.sort = match (column, self.sort) {
+            (Some(column), Some((sorted, descending))) if column == sorted => {
+                Some((column, !descending))
+            }
+            (Some(column), _) => Some((column, false)),
+            (None, _) => None,
+        };
+
+        // keep the same crackme selected wherever it ends up
+        let selected = self
+            .state
+            .selected()
+            .and_then(|i| self.found.get(i).copied());
+
+        self.found = self.ranked.clone();
+        self.apply_sort();
+
+        match selected.and_then(|s| self.found.iter().position(|&i| i == s)) {
+            Some(i) => self.state.select(Some(i)),
+            None => self.last(),
+        }
+    }
+
+    fn apply_sort(&mut self) {
+        if let Some((column, descending)) = self.sort {
+            let store = &self.store;
+            self.found.sort_by(|&a, &b| {
+                let ordering = column.compare(&store[a], &store[b]);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+    }
+
+    pub fn table(&self) -> Table<'static> {
+        let header = std::iter::once(Cell::from("")).chain(Column::ALL.iter().map(|&column| {
+            let arrow = match self.sort {
+                Some((sorted, false)) if sorted == column => " ▲",
+                Some((sorted, true)) if sorted == column => " ▼",
+                _ => "",
+            };
+            Cell::from(format!("{}{}", column.title(), arrow))
+        }));
+
+        let rows: Vec<Row> = self
             .found
             .iter()
-            .flat_map(|&i| self.store.get(i))
-            .map(|l| ListItem::new(format!("{} by {}", l.name(), l.author())))
+            .flat_map(|&i| self.store.get(i).map(|l| (i, l)))
+            .map(|(i, l)| {
+                let marker = if self.marked.contains(&i) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+
+                let found = self.matches.get(&i);
+
+                Row::new(vec![
+                    Cell::from(marker),
+                    Cell::from(Spans::from(highlight(l.name(), found.map(|f| &f.name[..])))),
+                    Cell::from(Spans::from(highlight(
+                        l.author(),
+                        found.map(|f| &f.author[..]),
+                    ))),
+                    Cell::from(l.language().to_string()),
+                    Cell::from(l.platform().to_string()),
+                    Cell::from(format!("{:.1}", l.stats().difficulty)),
+                    Cell::from(format!("{:.1}", l.stats().quality)),
+                    // the time doesn't fit, and the details pane has it anyway
+                    Cell::from(l.date().rsplit(' ').next().unwrap_or_default().to_string()),
+                    Cell::from(l.solutions().to_string()),
+                    Cell::from(l.comments().to_string()),
+                ])
+            })
             .collect();
 
-        List::new(items)
+        Table::new(rows)
+            .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+            .widths(&WIDTHS)
             .block(Block::default().borders(Borders::ALL))
             .highlight_symbol(">> ")
     }
 
     pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.found.len() - 1 {
-                    self.found.len() - 1
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
-        self.state.select(Some(i));
+        self.move_by(1);
     }
 
     pub fn previous(&mut self) {
+        self.move_by(-1);
+    }
+
+    /// Moves the selection down by `rows` (up if negative), stopping at the ends
+    pub fn move_by(&mut self, rows: isize) {
+        if self.found.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    0
-                } else {
-                    i - 1
-                }
-            }
+            Some(i) if rows < 0 => i.saturating_sub(rows.unsigned_abs()),
+            Some(i) => i.saturating_add(rows as usize).min(self.found.len() - 1),
             None => 0,
         };
         self.state.select(Some(i));
     }
 
+    /// How many rows fit in the table
+    pub fn page_size(&self) -> usize {
+        // the borders and the header take up three rows
+        (self.table_area.height as usize).saturating_sub(3).max(1)
+    }
+
+    /// Remembers where things are about to be drawn, so clicks can be matched up with them
+    pub fn set_areas(&mut self, table: Rect, preview: Rect) {
+        self.table_area = table;
+        self.preview_area = preview;
+
+        // the same as the table widget does, which keeps the selection on screen
+        if let Some(selected) = self.state.selected() {
+            let height = self.page_size();
+            let selected = selected.min(self.found.len().saturating_sub(1));
+
+            self.offset = self.offset.min(self.found.len().saturating_sub(1));
+            if selected >= self.offset + height {
+                self.offset = selected + 1 - height;
+            }
+            if selected < self.offset {
+                self.offset = selected;
+            }
+        }
+    }
+
+    /// The position in the table of the row at these screen coordinates
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.table_area;
+        // below the top border and the header, and above the bottom border
+        let first = area.y + 2;
+        let inside = column > area.x
+            && column < area.x + area.width.saturating_sub(1)
+            && row >= first
+            && row < area.y + area.height.saturating_sub(1);
+
+        if !inside {
+            return None;
+        }
+
+        Some(self.offset + (row - first) as usize).filter(|&i| i < self.found.len())
+    }
+
+    pub fn in_preview(&self, column: u16, row: u16) -> bool {
+        let area = self.preview_area;
+        column >= area.x
+            && column < area.x + area.width
+            && row >= area.y
+            && row < area.y + area.height
+    }
+
+    pub fn select(&mut self, i: usize) {
+        if i < self.found.len() {
+            self.state.select(Some(i));
+        }
+    }
+
+    /// How many lines the preview is scrolled down for the selected crackme
+    pub fn preview_scroll(&self) -> u16 {
+        match self.preview_scroll {
+            (lines, Some(i)) if Some(i) == self.selected_index() => lines,
+            _ => 0,
+        }
+    }
+
+    /// Scrolls the preview down by `lines` (up if negative)
+    pub fn scroll_preview(&mut self, lines: i32) {
+        let scrolled = (self.preview_scroll() as i32 + lines).max(0) as u16;
+        self.preview_scroll = (scrolled, self.selected_index());
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|i| self.found.get(i).copied())
+    }
+
+    pub fn show_help(&self) -> bool {
+        self.show_help
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    pub fn first(&mut self) {
+        if !self.found.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    /// Marks the selected crackme, or unmarks it if it already was
+    pub fn toggle_mark(&mut self) {
+        if let Some(&i) = self.state.selected().and_then(|i| self.found.get(i)) {
+            if !self.marked.remove(&i) {
+                self.marked.insert(i);
+            }
+        }
+    }
+
     pub fn selected(&self) -> Option<&ListCrackme<'_>> {
         self.state
             .selected()
@@ -153,13 +526,96 @@ impl<'a> Searcher<'a> {
     pub fn last(&mut self) {
         if !self.found.is_empty() {
             self.state.select(None);
+            self.offset = 0;
             self.state.select(Some(self.found.len() - 1));
         }
     }
 
-    pub fn into_selected(self) -> Option<&'a ListCrackme<'a>> {
-        self.state
-            .selected()
-            .and_then(move |i| self.store.get(self.found[i]))
+    /// The marked crackmes, or the selected one if none are marked
+    pub fn into_chosen(self) -> Vec<&'a ListCrackme<'static>> {
+        let store: &'a [ListCrackme<'static>] = self.store;
+
+        if self.marked.is_empty() {
+            self.state
+                .selected()
+                .and_then(|i| self.found.get(i))
+                .and_then(|&i| store.get(i))
+                .into_iter()
+                .collect()
+        } else {
+            self.marked.iter().flat_map(|&i| store.get(i)).collect()
+        }
+    }
+}
+
+// the comments or solutions, a blank line apart
+fn join<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(T::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// splits the text into spans so the characters at `indices` stand out
+fn highlight(text: &str, indices: Option<&[usize]>) -> Vec<Span<'static>> {
+    let indices = indices.unwrap_or_default();
+    let style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut current_highlighted = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let highlighted = indices.binary_search(&i).is_ok();
+        if highlighted != current_highlighted && !current.is_empty() {
+            let part = std::mem::take(&mut current);
+            spans.push(if current_highlighted {
+                Span::styled(part, style)
+            } else {
+                Span::raw(part)
+            });
+        }
+
+        current_highlighted = highlighted;
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        spans.push(if current_highlighted {
+            Span::styled(current, style)
+        } else {
+            Span::raw(current)
+        });
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::crackme;
+    use anyhow::anyhow;
+    use serde_json::json;
+
+    #[test]
+    fn failed_overviews_are_shown_not_retried() {
+        let mut store: Vec<_> = (0..10)
+            .map(|i| crackme(json!({ "id": format!("{:024}", i) })))
+            .collect();
+        let mut searcher = Searcher::new(&mut store);
+        searcher.select(5);
+
+        let wanted = searcher.wanted_overviews();
+        assert_eq!(wanted.first(), Some(&(5, format!("{:024}", 5))));
+        assert_eq!(wanted.len(), 7);
+        assert!(searcher.preview().contains("loading…"));
+
+        searcher.set_overview(5, Err(anyhow!("offline")));
+        assert!(searcher.preview().contains("Couldn't load: offline"));
+        assert!(searcher.wanted_overviews().iter().all(|&(i, _)| i != 5));
     }
 }
@@ -1,39 +1,99 @@
 use anyhow::Result;
-use std::io;
+use std::{
+    io,
+    ops::{Deref, DerefMut},
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+};
 
-use super::search::{SearchText, Searcher};
+use super::{input::SearchText, keys::Keymap, search::Searcher};
 
 use crossterm::{
+    cursor::Show,
     event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 
-use tui::layout::{Constraint, Direction, Layout};
-use tui::widgets::{Block, Borders, List, Paragraph, Wrap};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::Spans;
+use tui::widgets::{Block, Borders, Clear, Paragraph, Table, Tabs, Wrap};
 use tui::{backend::CrosstermBackend, Terminal};
 
 pub type Term = Terminal<CrosstermBackend<io::Stdout>>;
 
-pub fn get_term() -> Result<Term> {
+// set while the terminal is in raw mode on the alternate screen, so it's only restored once
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+/// The terminal, which is put back the way it was when this is dropped (even on errors and
+/// panics)
+pub struct TermGuard(Term);
+
+impl Deref for TermGuard {
+    type Target = Term;
+
+    fn deref(&self) -> &Term {
+        &self.0
+    }
+}
+
+impl DerefMut for TermGuard {
+    fn deref_mut(&mut self) -> &mut Term {
+        &mut self.0
+    }
+}
+
+impl Drop for TermGuard {
+    fn drop(&mut self) {
+        // there is nowhere left to report this to
+        let _ = restore();
+    }
+}
+
+/// Sets up the terminal for the picker, `mouse` turns on mouse capture (which stops the terminal
+/// from selecting text)
+pub fn get_term(mouse: bool) -> Result<TermGuard> {
+    // panics are printed by the default hook, which would end up on the alternate screen
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let _ = restore();
+            previous(info);
+        }));
+    });
+
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
-    let mut term = Terminal::new(backend)?;
+    let mut term = TermGuard(Terminal::new(backend)?);
 
+    ACTIVE.store(true, Ordering::SeqCst);
     enable_raw_mode()?;
-    term.backend_mut()
-        .execute(EnterAlternateScreen)?
-        .execute(EnableMouseCapture)?;
+    term.backend_mut().execute(EnterAlternateScreen)?;
+    if mouse {
+        term.backend_mut().execute(EnableMouseCapture)?;
+    }
     term.clear()?;
     Ok(term)
 }
 
-pub fn close_term(mut term: Term) -> Result<()> {
-    term.show_cursor()?;
-    disable_raw_mode()?;
-    term.backend_mut()
-        .execute(LeaveAlternateScreen)?
-        .execute(DisableMouseCapture)?;
+// undoes everything `get_term` did, if it hasn't been already
+fn restore() -> Result<()> {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        disable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            Show
+        )?;
+    }
+
     Ok(())
 }
 
@@ -41,11 +101,12 @@ pub fn draw<'a>(
     term: &mut Term,
     search_text: &SearchText,
     searcher: &mut Searcher<'a>,
+    keymap: &Keymap,
 ) -> Result<()> {
     term.draw(|f| {
         let whole = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
             .split(f.size());
 
         let mut chunks = Layout::default()
@@ -55,38 +116,64 @@ pub fn draw<'a>(
 
         chunks.push(whole[1]);
 
-        let items: List = searcher.list();
+        searcher.set_areas(chunks[0], chunks[2]);
+
+        let items: Table = searcher.table();
 
         f.render_stateful_widget(items, chunks[0], searcher.state());
 
-        let width = chunks[1].width;
-        let text = search_text.get(width as usize);
+        let (text, cursor) = search_text.view(chunks[1].width as usize);
 
         let search_block = Paragraph::new(text).block(Block::default().borders(Borders::ALL));
         f.render_widget(search_block, chunks[1]);
 
-        f.set_cursor(
-            chunks[1].x
-                + 1
-                + if (width as usize - 1) < text.len() {
-                    width
-                } else {
-                    text.len() as u16
-                },
-            chunks[1].y + 1,
-        );
-
-        let description = Paragraph::new(
-            searcher
-                .selected()
-                .map(|crackme| crackme.to_string())
-                .unwrap_or_default(),
-        )
-        .block(Block::default().borders(Borders::ALL))
-        .wrap(Wrap { trim: false });
-
-        f.render_widget(description, chunks[2]);
+        f.set_cursor(chunks[1].x + 1 + cursor, chunks[1].y + 1);
+
+        let preview = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(chunks[2]);
+
+        let titles = searcher.tab_titles().into_iter().map(Spans::from).collect();
+        let tabs = Tabs::new(titles)
+            .block(Block::default().borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+            .select(searcher.tab());
+
+        f.render_widget(tabs, preview[0]);
+
+        let description = Paragraph::new(searcher.preview())
+            .block(Block::default().borders(Borders::ALL))
+            .wrap(Wrap { trim: false })
+            .scroll((searcher.preview_scroll(), 0));
+
+        f.render_widget(description, preview[1]);
+
+        if searcher.show_help() {
+            let area = centered(f.size(), 70, 80);
+            let help = Paragraph::new(keymap.help().join("\n")).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Keys (letters only work with an empty search, any key closes this)"),
+            );
+
+            f.render_widget(Clear, area);
+            f.render_widget(help, area);
+        }
     })?;
 
     Ok(())
 }
+
+// a rectangle in the middle of `area` taking up the given percentages of it
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = area.width * width / 100;
+    let height = area.height * height / 100;
+
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
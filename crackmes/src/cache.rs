@@ -0,0 +1,220 @@
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// makes the temporary file of every write unique within the process
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// The kinds of pages that get cached, each of which can be kept for a different time
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PageKind {
+    Latest,
+    Search,
+    Overview,
+}
+
+impl PageKind {
+    fn default_ttl(self) -> Duration {
+        match self {
+            // new crackmes show up on these all the time
+            PageKind::Latest | PageKind::Search => Duration::from_secs(10 * 60),
+            // comments and solutions don't come in that often
+            PageKind::Overview => Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// Pages saved in a directory, keyed by their URL (and form, for searches), so they don't have to
+/// be downloaded every time
+///
+/// There is no size limit, old pages are only replaced when the same page is fetched again, so
+/// the directory keeps growing until `clear` is called.
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    dir: PathBuf,
+    ttl: HashMap<PageKind, Duration>,
+    offline: bool,
+}
+
+impl PageCache {
+    pub fn new(dir: impl Into<PathBuf>) -> PageCache {
+        PageCache {
+            dir: dir.into(),
+            ttl: HashMap::new(),
+            offline: false,
+        }
+    }
+
+    /// How long pages of `kind` are used before asking the site whether they changed
+    pub fn ttl(mut self, kind: PageKind, ttl: Duration) -> PageCache {
+        self.ttl.insert(kind, ttl);
+        self
+    }
+
+    /// Only use cached pages, anything that isn't cached fails instead of being downloaded
+    pub fn offline(mut self, offline: bool) -> PageCache {
+        self.offline = offline;
+        self
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub(crate) fn ttl_of(&self, kind: PageKind) -> Duration {
+        self.ttl
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| kind.default_ttl())
+    }
+
+    // missing and unreadable entries are both just not cached
+    pub(crate) fn load(&self, key: &str) -> Option<Entry> {
+        Entry::parse(&fs::read_to_string(self.path(key)).ok()?)
+    }
+
+    pub(crate) fn store(&self, key: &str, entry: &Entry) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // written next to it first, so a half written entry is never read, and under a name of
+        // its own so two writers of the same page don't write into the same file
+        let path = self.path(key);
+        let write = WRITES.fetch_add(1, Ordering::Relaxed);
+        let part = path.with_extension(format!("{}.{}.part", process::id(), write));
+
+        let written = fs::write(&part, entry.to_text()).and_then(|()| fs::rename(&part, path));
+        if written.is_err() {
+            let _ = fs::remove_file(&part);
+        }
+
+        written
+    }
+
+    /// Deletes every cached page (and any half written one), returning how many files went
+    pub fn clear(&self) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            entries => entries?,
+        };
+
+        let mut removed = 0;
+        for entry in entries {
+            let entry = entry?;
+            // only what `store` wrote, in case the directory is shared with something else
+            let name = entry.file_name();
+            let hash = name
+                .to_str()
+                .and_then(|name| name.get(..64))
+                .unwrap_or_default();
+
+            if hash.len() == 64 && hex::decode(hash).is_ok() && entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(hex::encode(Sha256::digest(key.as_bytes())))
+    }
+}
+
+/// A cached page and what we need to ask the site whether it changed
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Entry {
+    pub fetched: SystemTime,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl Entry {
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.fetched)
+            .unwrap_or_default()
+    }
+
+    // the fetch time, ETag and Last-Modified each on a line (empty if missing), then the page
+    fn parse(text: &str) -> Option<Entry> {
+        let mut parts = text.splitn(4, '\n');
+        let seconds = parts.next()?.parse().ok()?;
+        let header = |value: &str| Some(value.to_string()).filter(|v| !v.is_empty());
+
+        Some(Entry {
+            fetched: UNIX_EPOCH + Duration::from_secs(seconds),
+            etag: header(parts.next()?),
+            last_modified: header(parts.next()?),
+            body: parts.next()?.to_string(),
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let seconds = self
+            .fetched
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        format!(
+            "{}\n{}\n{}\n{}",
+            seconds,
+            self.etag.as_deref().unwrap_or_default(),
+            self.last_modified.as_deref().unwrap_or_default(),
+            self.body
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_round_trip() {
+        let entry = Entry {
+            fetched: UNIX_EPOCH + Duration::from_secs(1_620_000_000),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            body: "<html>\n\n</html>".to_string(),
+        };
+
+        assert_eq!(Entry::parse(&entry.to_text()), Some(entry));
+    }
+
+    #[test]
+    fn clear_only_removes_entries() {
+        let dir = std::env::temp_dir().join(format!("crackmes-clear-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = PageCache::new(&dir);
+
+        assert_eq!(cache.clear().unwrap(), 0);
+
+        let entry = Entry {
+            fetched: UNIX_EPOCH + Duration::from_secs(1_620_000_000),
+            etag: None,
+            last_modified: None,
+            body: "<html></html>".to_string(),
+        };
+        cache.store("/lasts/1", &entry).unwrap();
+        cache.store("/lasts/1", &entry).unwrap();
+        cache.store("/lasts/2", &entry).unwrap();
+        fs::write(dir.join("notes.txt"), "not ours").unwrap();
+
+        assert_eq!(cache.load("/lasts/1"), Some(entry));
+        // no temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.load("/lasts/1"), None);
+        assert!(dir.join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,586 @@
+use crate::{
+    cache::{Entry, PageCache, PageKind},
+    error::{CrackmeError, CrackmeResult},
+    list::{self, ListCrackme, ListPage},
+    overview::OverviewCrackme,
+    search::SearchQuery,
+};
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
+use scraper::{Html, Selector};
+use std::{future::Future, time::SystemTime};
+
+pub const DEFAULT_BASE_URL: &str = "https://crackmes.one";
+
+/// Async client for [crackmes.one](https://crackmes.one) that returns the parsed crackmes
+#[derive(Debug, Clone)]
+pub struct CrackmesClient {
+    client: Client,
+    base_url: String,
+    cache: Option<PageCache>,
+}
+
+impl CrackmesClient {
+    pub fn new() -> CrackmeResult<CrackmesClient> {
+        CrackmesClient::with_base_url(DEFAULT_BASE_URL)
+    }
+
+    /// Creates a client that talks to `base_url` instead of crackmes.one (i.e. a mock server)
+    pub fn with_base_url(base_url: impl Into<String>) -> CrackmeResult<CrackmesClient> {
+        // the search token is tied to the session cookie, so we need a cookie store
+        let client = Client::builder().cookie_store(true).build()?;
+
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+
+        Ok(CrackmesClient {
+            client,
+            base_url,
+            cache: None,
+        })
+    }
+
+    /// Keeps the pages it downloads (not the archives) in `cache`, and uses them while they're
+    /// fresh or when offline
+    pub fn cached(mut self, cache: PageCache) -> CrackmesClient {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn overview(&self, id: &str) -> CrackmeResult<OverviewCrackme<'static>> {
+        let body = self
+            .cached_page(PageKind::Overview, &format!("/crackme/{}", id))
+            .await?;
+        let html = Html::parse_document(&body);
+
+        Ok(OverviewCrackme::with_full_html(&html, id)?.into_owned())
+    }
+
+    pub async fn latest(&self, page: u64) -> CrackmeResult<Vec<ListCrackme<'static>>> {
+        Ok(self.latest_page(page).await?.crackmes)
+    }
+
+    /// Like `latest`, but also returns where the pagination ends
+    pub async fn latest_page(&self, page: u64) -> CrackmeResult<ListPage<'static>> {
+        let body = self
+            .cached_page(PageKind::Latest, &format!("/lasts/{}", page))
+            .await?;
+
+        parse_owned_page(&body)
+    }
+
+    pub async fn search(&self, query: &SearchQuery) -> CrackmeResult<Vec<ListCrackme<'static>>> {
+        Ok(self.search_page(query).await?.crackmes)
+    }
+
+    /// Like `search`, but also returns where the pagination ends
+    pub async fn search_page(&self, query: &SearchQuery) -> CrackmeResult<ListPage<'static>> {
+        let url = self.url("/search");
+
+        // the token changes with every session, so it's left out of the key
+        let form: Vec<String> = query
+            .to_form("")
+            .iter()
+            .filter(|(name, _)| *name != "token")
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        let key = format!("{} {}", url, form.join("&"));
+
+        // only asked for if the results aren't cached
+        let request = || async {
+            let body = self.get_page("/search").await?;
+            let token = get_token(&Html::parse_document(&body))?;
+
+            Ok(self.client.post(&url).form(&query.to_form(&token)))
+        };
+
+        let body = self.fetch_cached(PageKind::Search, &key, request).await?;
+
+        parse_owned_page(&body)
+    }
+
+    /// Downloads the crackme's zip archive
+    pub async fn download(&self, id: &str) -> CrackmeResult<Vec<u8>> {
+        let url = self.url(&format!("/static/crackme/{}.zip", id));
+        self.check_online(&url)?;
+
+        let bytes = self
+            .client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(bytes.to_vec())
+    }
+
+    /// Starts streaming the crackme's zip archive, skipping the first `offset` bytes if the server
+    /// lets us (check `Download::offset` for where it actually starts)
+    ///
+    /// `validator` is the `Download::validator` of the download the first `offset` bytes came
+    /// from, the server only skips them if the archive hasn't changed since. Without one it always
+    /// starts over, since there is no way to tell the bytes still belong to the same archive.
+    pub async fn download_from(
+        &self,
+        id: &str,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> CrackmeResult<Download> {
+        let url = self.url(&format!("/static/crackme/{}.zip", id));
+        self.check_online(&url)?;
+
+        let mut request = self.client.get(&url);
+        if let (true, Some(validator)) = (offset > 0, validator) {
+            request = request
+                .header(header::RANGE, format!("bytes={}-", offset))
+                .header(header::IF_RANGE, validator);
+        }
+
+        let response = request.send().await?;
+
+        let response = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                match content_range(&response) {
+                    Some((start, _)) if start == offset => {
+                        return Ok(Download { response, offset })
+                    }
+                    // not the part we asked for, so it can't be appended to what we have
+                    _ => self.client.get(&url).send().await?.error_for_status()?,
+                }
+            }
+            // the range didn't make sense (i.e. the file shrunk), so start over
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                self.client.get(&url).send().await?.error_for_status()?
+            }
+            // the server ignored the range, or the archive changed, and is sending everything
+            _ => response.error_for_status()?,
+        };
+
+        Ok(Download {
+            response,
+            offset: 0,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    // archives aren't cached, so there is nothing to fall back on when offline
+    fn check_online(&self, url: &str) -> CrackmeResult<()> {
+        match &self.cache {
+            Some(cache) if cache.is_offline() => Err(CrackmeError::Offline(url.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    async fn cached_page(&self, kind: PageKind, path: &str) -> CrackmeResult<String> {
+        let url = self.url(path);
+
+        self.fetch_cached(kind, &url, || async { Ok(self.client.get(&url)) })
+            .await
+    }
+
+    // the cached page if it's fresh enough (or we're offline), otherwise sends `request` (only
+    // asking for the page if it changed when there is an older copy) and caches what comes back
+    async fn fetch_cached<F, Fut>(
+        &self,
+        kind: PageKind,
+        key: &str,
+        request: F,
+    ) -> CrackmeResult<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = CrackmeResult<RequestBuilder>>,
+    {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => {
+                let response = request().await?.send().await?;
+                return Ok(response.error_for_status()?.text().await?);
+            }
+        };
+
+        let entry = cache.load(key);
+
+        if cache.is_offline() {
+            return entry
+                .map(|entry| entry.body)
+                .ok_or_else(|| CrackmeError::Offline(key.to_string()));
+        }
+
+        if let Some(entry) = entry.as_ref().filter(|e| e.age() < cache.ttl_of(kind)) {
+            return Ok(entry.body.clone());
+        }
+
+        let response = async {
+            let mut request = request().await?;
+
+            if let Some(entry) = &entry {
+                if let Some(etag) = &entry.etag {
+                    request = request.header(header::IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &entry.last_modified {
+                    request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            Ok::<_, CrackmeError>(request.send().await?)
+        }
+        .await;
+
+        let response = match (response, entry) {
+            // an old page is better than none when the network is down
+            (Err(CrackmeError::Request(e)), Some(entry)) if e.is_connect() || e.is_timeout() => {
+                return Ok(entry.body)
+            }
+            (Err(e), _) => return Err(e),
+            (Ok(response), Some(entry)) if response.status() == StatusCode::NOT_MODIFIED => {
+                let entry = Entry {
+                    fetched: SystemTime::now(),
+                    ..entry
+                };
+                // not being able to cache it doesn't make the page any less usable
+                let _ = cache.store(key, &entry);
+
+                return Ok(entry.body);
+            }
+            (Ok(response), _) => response.error_for_status()?,
+        };
+
+        let value = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        let etag = value(header::ETAG);
+        let last_modified = value(header::LAST_MODIFIED);
+
+        let entry = Entry {
+            fetched: SystemTime::now(),
+            etag,
+            last_modified,
+            body: response.text().await?,
+        };
+        let _ = cache.store(key, &entry);
+
+        Ok(entry.body)
+    }
+
+    async fn get_page(&self, path: &str) -> CrackmeResult<String> {
+        let body = self
+            .client
+            .get(self.url(path))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(body)
+    }
+}
+
+/// A crackme archive that is being downloaded
+#[derive(Debug)]
+pub struct Download {
+    response: Response,
+    offset: u64,
+}
+
+impl Download {
+    /// The byte of the archive that the first chunk starts at
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The size of the whole archive, if the server told us
+    pub fn total(&self) -> Option<u64> {
+        match content_range(&self.response) {
+            Some((_, total)) => total,
+            None => self.response.content_length(),
+        }
+    }
+
+    /// What to pass to `download_from` when resuming this download, so it only resumes if the
+    /// archive is still the same one
+    pub fn validator(&self) -> Option<&str> {
+        let headers = self.response.headers();
+
+        // weak ETags aren't allowed in `If-Range`
+        headers
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .filter(|etag| !etag.starts_with("W/"))
+            .or_else(|| {
+                headers
+                    .get(header::LAST_MODIFIED)
+                    .and_then(|date| date.to_str().ok())
+            })
+    }
+
+    /// The next part of the archive, `None` once it is done
+    pub async fn chunk(&mut self) -> CrackmeResult<Option<Vec<u8>>> {
+        Ok(self.response.chunk().await?.map(|bytes| bytes.to_vec()))
+    }
+}
+
+// the first byte and the archive's size (if known) from a `Content-Range: bytes a-b/size` header
+fn content_range(response: &Response) -> Option<(u64, Option<u64>)> {
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let (range, total) = range.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+fn parse_owned_page(body: &str) -> CrackmeResult<ListPage<'static>> {
+    let html = Html::parse_document(body);
+
+    Ok(list::parse_page(&html)?.into_owned())
+}
+
+// returns the token to allow searching
+fn get_token(html: &Html) -> CrackmeResult<String> {
+    let selector = Selector::parse("#token").unwrap();
+
+    let token = html
+        .select(&selector)
+        .next()
+        .and_then(|t| t.value().attr("value"))
+        .ok_or(CrackmeError::NotFound("token"))?;
+
+    Ok(token.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use wiremock::{
+        matchers::{header, header_exists, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+    const TEST_LATEST_FILE: &str = include_str!("../static/latest_test.html");
+
+    // a fresh cache directory for each test
+    fn test_cache(name: &str) -> PageCache {
+        let dir = std::env::temp_dir().join(format!("crackmes-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        PageCache::new(dir)
+    }
+
+    #[tokio::test]
+    async fn latest_from_mock() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/lasts/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TEST_LATEST_FILE))
+            .mount(&server)
+            .await;
+
+        let client = CrackmesClient::with_base_url(server.uri()).unwrap();
+        let crackmes = client.latest(1).await.unwrap();
+
+        assert_eq!(
+            crackmes.first().map(ListCrackme::id),
+            Some("60957b9a33c5d458ce0ec88e")
+        );
+    }
+
+    #[tokio::test]
+    async fn download_from_mock() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/static/crackme/60957b9a33c5d458ce0ec88e.zip"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(&b"PK\x05\x06"[..]))
+            .mount(&server)
+            .await;
+
+        let client = CrackmesClient::with_base_url(server.uri()).unwrap();
+        let bytes = client.download("60957b9a33c5d458ce0ec88e").await.unwrap();
+
+        assert_eq!(bytes, b"PK\x05\x06");
+    }
+
+    #[tokio::test]
+    async fn resume_download() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/static/crackme/60957b9a33c5d458ce0ec88e.zip"))
+            .and(header("Range", "bytes=2-"))
+            .and(header("If-Range", "\"v1\""))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("Content-Range", "bytes 2-3/4")
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_bytes(&b"\x05\x06"[..]),
+            )
+            .mount(&server)
+            .await;
+
+        let client = CrackmesClient::with_base_url(server.uri()).unwrap();
+        let mut download = client
+            .download_from("60957b9a33c5d458ce0ec88e", 2, Some("\"v1\""))
+            .await
+            .unwrap();
+
+        assert_eq!(download.offset(), 2);
+        assert_eq!(download.total(), Some(4));
+        assert_eq!(download.validator(), Some("\"v1\""));
+        assert_eq!(download.chunk().await.unwrap(), Some(b"\x05\x06".to_vec()));
+        assert_eq!(download.chunk().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn changed_archive_starts_over() {
+        // like a server whose archive isn't `"v1"` anymore, it ignores the range
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/static/crackme/60957b9a33c5d458ce0ec88e.zip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v2\"")
+                    .set_body_bytes(&b"PK\x05\x06\x07"[..]),
+            )
+            .mount(&server)
+            .await;
+
+        let client = CrackmesClient::with_base_url(server.uri()).unwrap();
+        let mut download = client
+            .download_from("60957b9a33c5d458ce0ec88e", 2, Some("\"v1\""))
+            .await
+            .unwrap();
+
+        assert_eq!(download.offset(), 0);
+        assert_eq!(download.total(), Some(5));
+        assert_eq!(download.validator(), Some("\"v2\""));
+        assert_eq!(
+            download.chunk().await.unwrap(),
+            Some(b"PK\x05\x06\x07".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn no_validator_doesnt_resume() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/static/crackme/60957b9a33c5d458ce0ec88e.zip"))
+            .and(header_exists("Range"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/static/crackme/60957b9a33c5d458ce0ec88e.zip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Last-Modified", "Fri, 07 May 2021 17:40:00 GMT")
+                    .set_body_bytes(&b"PK\x05\x06"[..]),
+            )
+            .mount(&server)
+            .await;
+
+        let client = CrackmesClient::with_base_url(server.uri()).unwrap();
+        let download = client
+            .download_from("60957b9a33c5d458ce0ec88e", 2, None)
+            .await
+            .unwrap();
+
+        assert_eq!(download.offset(), 0);
+        assert_eq!(download.validator(), Some("Fri, 07 May 2021 17:40:00 GMT"));
+    }
+
+    #[tokio::test]
+    async fn cached_page_is_reused() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/lasts/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TEST_LATEST_FILE))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = CrackmesClient::with_base_url(server.uri())
+            .unwrap()
+            .cached(test_cache("reused"));
+
+        let first = client.latest(1).await.unwrap();
+        let second = client.latest(1).await.unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn stale_page_is_revalidated() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/lasts/1"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/lasts/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_string(TEST_LATEST_FILE),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        let cache = test_cache("revalidated").ttl(PageKind::Latest, Duration::from_secs(0));
+        let client = CrackmesClient::with_base_url(server.uri())
+            .unwrap()
+            .cached(cache);
+
+        let first = client.latest(1).await.unwrap();
+        let second = client.latest(1).await.unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn offline_only_uses_cache() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/lasts/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TEST_LATEST_FILE))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cache = test_cache("offline");
+        let client = CrackmesClient::with_base_url(server.uri()).unwrap();
+
+        let online = client.clone().cached(cache.clone());
+        let offline = client.cached(cache.offline(true));
+
+        assert!(matches!(
+            offline.latest(1).await,
+            Err(CrackmeError::Offline(_))
+        ));
+        assert_eq!(
+            online.latest(1).await.unwrap(),
+            offline.latest(1).await.unwrap()
+        );
+        assert!(matches!(
+            offline.download("60957b9a33c5d458ce0ec88e").await,
+            Err(CrackmeError::Offline(_))
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::error::{CrackmeError, CrackmeResult};
+use scraper::{ElementRef, Html, Selector};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
+
+/// A comment left on a crackme
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Comment<'html> {
+    author: Cow<'html, str>,
+    date: Cow<'html, str>,
+    body: Cow<'html, str>,
+}
+
+impl<'html> fmt::Display for Comment<'html> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} on {}:", self.author, self.date)?;
+        writeln!(f, "{}", self.body)
+    }
+}
+
+impl<'a> Comment<'a> {
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Converts into a comment that no longer borrows from the `Html` it was parsed from
+    pub fn into_owned(self) -> Comment<'static> {
+        Comment {
+            author: Cow::Owned(self.author.into_owned()),
+            date: Cow::Owned(self.date.into_owned()),
+            body: Cow::Owned(self.body.into_owned()),
+        }
+    }
+}
+
+/// Parses the comments on a crackme's overview page, leaving out ones that can't be parsed (i.e.
+/// from deleted users) so one odd comment doesn't hide the crackme
+pub fn parse_comments(html: &Html) -> Vec<Comment<'_>> {
+    html.select(&comments_selector())
+        .filter_map(|p| parse_comment(p).ok())
+        .collect()
+}
+
+/// How many comments the page has, including any that `parse_comments` leaves out
+pub fn count_comments(html: &Html) -> usize {
+    html.select(&comments_selector()).count()
+}
+
+fn comments_selector() -> Selector {
+    Selector::parse("div#comments p").unwrap()
+}
+
+fn parse_comment(p: ElementRef<'_>) -> CrackmeResult<Comment<'_>> {
+    let author = p
+        .select(&Selector::parse("a[href^=\"/user/\"]").unwrap())
+        .next()
+        .and_then(|a| a.text().next())
+        .map(str::trim)
+        .ok_or(CrackmeError::NotFound("comment author"))?;
+
+    // Input after the author looks like "on 12:44 PM 04/22/2021: COMMENT"
+    // the time has a colon in it too, but never followed by a space
+    let rest = p
+        .text()
+        .skip_while(|t| t.trim() != author)
+        .skip(1)
+        .collect::<String>();
+
+    let (date, body) = rest
+        .trim_start()
+        .strip_prefix("on ")
+        .and_then(|rest| {
+            rest.find(": ")
+                .map(|i| (&rest[..i], &rest[i + 2..]))
+                .or_else(|| rest.strip_suffix(':').map(|date| (date, "")))
+        })
+        .ok_or(CrackmeError::NotFound("comment date"))?;
+
+    let comment = Comment {
+        author: author.into(),
+        date: date.trim().to_string().into(),
+        // collapse whitespace the same way a browser would render it
+        body: body.split_whitespace().collect::<Vec<_>>().join(" ").into(),
+    };
+
+    Ok(comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const TEST_FILE: &str = include_str!("../static/test.html");
+
+    #[test]
+    fn parse_fixture_comments() {
+        let html = Html::parse_document(TEST_FILE);
+        let comments = parse_comments(&html);
+
+        assert_eq!(comments.len(), count_comments(&html));
+        assert_eq!(comments.len(), 2);
+
+        // every field has to put the comment back together exactly as the page shows it
+        let paragraphs = comments_selector();
+        let author_link = Selector::parse("a[href^=\"/user/\"]").unwrap();
+
+        for (comment, p) in comments.iter().zip(html.select(&paragraphs)) {
+            let shown = p.text().collect::<String>();
+            let shown = shown.split_whitespace().collect::<Vec<_>>().join(" ");
+            let link = p
+                .select(&author_link)
+                .next()
+                .and_then(|a| a.value().attr("href"));
+
+            assert!(!comment.author().is_empty());
+            assert_eq!(link, Some(format!("/user/{}", comment.author()).as_str()));
+            // like "12:44 PM 04/22/2021"
+            let day = comment.date().rsplit(' ').next().unwrap_or_default();
+            assert!(comment.date().contains("M "), "{:?}", comment);
+            assert_eq!(day.split('/').count(), 3, "{:?}", comment);
+            assert!(!comment.body().is_empty());
+            assert_eq!(
+                shown,
+                format!(
+                    "{} on {}: {}",
+                    comment.author(),
+                    comment.date(),
+                    comment.body()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn parse_comment_text() {
+        let html = Html::parse_document(
+            r#"<div id="comments">
+                <p><a href="/user/someone">someone</a> on 8:28 PM 04/23/2021: broken on win10,
+                <a href="/crackme/60816fca33c5d42f38520831">this one</a> works</p>
+            </div>"#,
+        );
+
+        let comments = parse_comments(&html);
+
+        assert_eq!(
+            comments,
+            vec![Comment {
+                author: "someone".into(),
+                date: "8:28 PM 04/23/2021".into(),
+                body: "broken on win10, this one works".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn skip_broken_comment() {
+        let html = Html::parse_document(
+            r#"<div id="comments">
+                <p>[deleted] on 1:00 PM 04/22/2021: gone</p>
+                <p><a href="/user/oles">oles</a> on 12:50 PM 04/22/2021: thanks for trying it</p>
+                <p><a href="/user/someone">someone</a> on 8:28 PM 04/23/2021:</p>
+            </div>"#,
+        );
+
+        assert_eq!(count_comments(&html), 3);
+        assert_eq!(
+            parse_comments(&html),
+            vec![
+                Comment {
+                    author: "oles".into(),
+                    date: "12:50 PM 04/22/2021".into(),
+                    body: "thanks for trying it".into(),
+                },
+                Comment {
+                    author: "someone".into(),
+                    date: "8:28 PM 04/23/2021".into(),
+                    body: "".into(),
+                },
+            ]
+        );
+    }
+}
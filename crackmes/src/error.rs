@@ -11,4 +11,15 @@ pub enum CrackmeError {
 
     #[error("Failed to parse {0}!")]
     DetailParse(&'static str),
+
+    #[error("Invalid {0} range {1}..{2}!")]
+    InvalidRange(&'static str, u8, u8),
+
+    #[cfg(feature = "client")]
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[cfg(feature = "client")]
+    #[error("Not cached yet, and offline: {0}")]
+    Offline(String),
 }
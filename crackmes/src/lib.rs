@@ -1,28 +1,56 @@
 //! Library for parsing crackmes from [crackmes.one](https://crackmes.one)
 
+#[cfg(feature = "client")]
+pub mod cache;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod comment;
 pub mod error;
 pub mod list;
 mod macros;
 pub mod overview;
+pub mod search;
+pub mod solution;
 
+#[cfg(feature = "client")]
+pub use client::CrackmesClient;
 pub use scraper::{Html, Selector};
 
-use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
 use strum::{Display, EnumString};
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub(crate) struct BaseCrackme<'html> {
-    name: &'html str,
-    author: &'html str,
+    name: Cow<'html, str>,
+    author: Cow<'html, str>,
     language: Language,
-    date: &'html str,
+    date: Cow<'html, str>,
     platform: Platform,
     stats: Stats,
-    id: &'html str,
+    id: Cow<'html, str>,
     solutions: u64,
     comments: u64,
 }
 
+impl<'html> BaseCrackme<'html> {
+    pub(crate) fn into_owned(self) -> BaseCrackme<'static> {
+        BaseCrackme {
+            name: Cow::Owned(self.name.into_owned()),
+            author: Cow::Owned(self.author.into_owned()),
+            language: self.language,
+            date: Cow::Owned(self.date.into_owned()),
+            platform: self.platform,
+            stats: self.stats,
+            id: Cow::Owned(self.id.into_owned()),
+            solutions: self.solutions,
+            comments: self.comments,
+        }
+    }
+}
+
 impl<'html> fmt::Display for BaseCrackme<'html> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Name: {}", self.name)?;
@@ -40,6 +68,7 @@ impl<'html> fmt::Display for BaseCrackme<'html> {
 // we allow this so the mapping is more one to one
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, EnumString, Display, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Platform {
     DOS,
     #[strum(serialize = "macos", serialize = "Mac OS X")]
@@ -66,6 +95,7 @@ pub enum Platform {
 }
 
 #[derive(Debug, PartialEq, EnumString, Display, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Language {
     #[strum(serialize = "cpp", serialize = "C/C++")]
     COrCPlusPlus,
@@ -84,6 +114,7 @@ pub enum Language {
 }
 
 #[derive(Debug, PartialEq, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Stats {
     pub quality: f32,
     pub difficulty: f32,
@@ -4,10 +4,14 @@ use crate::{
     next_parse, Language, Platform, Stats,
 };
 use scraper::{Html, Selector};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ListCrackme<'html> {
+    #[cfg_attr(feature = "serde", serde(flatten))]
     base: BaseCrackme<'html>,
     description: Option<String>,
 }
@@ -48,15 +52,39 @@ impl<'a> ListCrackme<'a> {
     }
 
     pub fn name(&self) -> &str {
-        self.base.name
+        &self.base.name
     }
 
     pub fn author(&self) -> &str {
-        self.base.author
+        &self.base.author
     }
 
     pub fn id(&self) -> &str {
-        self.base.id
+        &self.base.id
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.base.language
+    }
+
+    pub fn platform(&self) -> &Platform {
+        &self.base.platform
+    }
+
+    pub fn date(&self) -> &str {
+        &self.base.date
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.base.stats
+    }
+
+    pub fn solutions(&self) -> u64 {
+        self.base.solutions
+    }
+
+    pub fn comments(&self) -> u64 {
+        self.base.comments
     }
 
     pub fn try_set_description(&mut self, s: String) -> Result<(), String> {
@@ -67,6 +95,63 @@ impl<'a> ListCrackme<'a> {
             Err(s)
         }
     }
+
+    /// Converts into a crackme that no longer borrows from the `Html` it was parsed from
+    pub fn into_owned(self) -> ListCrackme<'static> {
+        ListCrackme {
+            base: self.base.into_owned(),
+            description: self.description,
+        }
+    }
+}
+
+/// One page of a listing, along with where the pagination says the listing ends
+#[derive(Debug, PartialEq, Clone)]
+pub struct ListPage<'html> {
+    pub crackmes: Vec<ListCrackme<'html>>,
+    pub last_page: Option<u64>,
+}
+
+impl<'a> ListPage<'a> {
+    /// Converts into a page that no longer borrows from the `Html` it was parsed from
+    pub fn into_owned(self) -> ListPage<'static> {
+        ListPage {
+            crackmes: self
+                .crackmes
+                .into_iter()
+                .map(ListCrackme::into_owned)
+                .collect(),
+            last_page: self.last_page,
+        }
+    }
+}
+
+pub fn parse_page(html: &Html) -> CrackmeResult<ListPage<'_>> {
+    let page = ListPage {
+        crackmes: parse_list(html)?,
+        last_page: parse_last_page(html),
+    };
+
+    Ok(page)
+}
+
+/// Finds the highest page number in the pagination links, `None` if there is no pagination
+pub fn parse_last_page(html: &Html) -> Option<u64> {
+    let selector = Selector::parse("ul.pagination a").unwrap();
+
+    // links look like "/lasts/3" (or "?page=3"), but fall back to the link's text for the ones
+    // that aren't plain links
+    html.select(&selector)
+        .flat_map(|a| {
+            let href = a
+                .value()
+                .attr("href")
+                .and_then(|link| link.rsplit(&['/', '='][..]).next());
+
+            href.into_iter().chain(a.text().map(str::trim))
+        })
+        .filter_map(|n| n.parse().ok())
+        .max()
 }
 
 pub fn parse_list(html: &Html) -> CrackmeResult<Vec<ListCrackme<'_>>> {
@@ -124,13 +209,13 @@ pub fn parse_row<'a>(
     let stats = Stats::new(quality, difficulty);
 
     let base = BaseCrackme {
-        name,
-        author,
+        name: name.into(),
+        author: author.into(),
         language,
-        date,
+        date: date.into(),
         platform,
         stats,
-        id,
+        id: id.into(),
         solutions,
         comments,
     };
@@ -159,16 +244,16 @@ mod test {
             crackmes.first(),
             Some(&ListCrackme {
                 base: BaseCrackme {
-                    name: "EZwan",
-                    author: "DirkD",
+                    name: "EZwan".into(),
+                    author: "DirkD".into(),
                     language: Language::COrCPlusPlus,
                     platform: Platform::UnixLinux,
-                    date: "5:40 PM 05/07/2021",
+                    date: "5:40 PM 05/07/2021".into(),
                     stats: Stats {
                         quality: 4.0,
                         difficulty: 1.0
                     },
-                    id: "60957b9a33c5d458ce0ec88e",
+                    id: "60957b9a33c5d458ce0ec88e".into(),
                     solutions: 0,
                     comments: 1,
                 },
@@ -187,16 +272,16 @@ mod test {
             crackmes.first(),
             Some(&ListCrackme {
                 base: BaseCrackme {
-                    name: "EZwan",
-                    author: "DirkD",
+                    name: "EZwan".into(),
+                    author: "DirkD".into(),
                     language: Language::COrCPlusPlus,
                     platform: Platform::UnixLinux,
-                    date: "5:40 PM 05/07/2021",
+                    date: "5:40 PM 05/07/2021".into(),
                     stats: Stats {
                         quality: 4.0,
                         difficulty: 1.0
                     },
-                    id: "60957b9a33c5d458ce0ec88e",
+                    id: "60957b9a33c5d458ce0ec88e".into(),
                     solutions: 0,
                     comments: 0,
                 },
@@ -204,4 +289,37 @@ mod test {
             })
         );
     }
+
+    #[test]
+    fn parse_pagination() {
+        let html = Html::parse_document(
+            r#"<ul class="pagination">
+                <li class="page-item"><a href="/lasts/1">Prev</a></li>
+                <li class="page-item"><a href="/lasts/1">1</a></li>
+                <li class="page-item active"><a href="/lasts/2">2</a></li>
+                <li class="page-item"><a href="/lasts/17">17</a></li>
+                <li class="page-item"><a href="/lasts/3">Next</a></li>
+            </ul>"#,
+        );
+
+        assert_eq!(parse_last_page(&html), Some(17));
+        assert_eq!(parse_last_page(&Html::parse_document("")), None);
+    }
+
+    #[test]
+    fn owned_outlives_html() {
+        let crackmes: Vec<ListCrackme<'static>> = {
+            let html = Html::parse_document(TEST_LATEST_FILE);
+            parse_list(&html)
+                .unwrap()
+                .into_iter()
+                .map(ListCrackme::into_owned)
+                .collect()
+        };
+
+        assert_eq!(
+            crackmes.first().map(ListCrackme::id),
+            Some("60957b9a33c5d458ce0ec88e")
+        );
+    }
 }
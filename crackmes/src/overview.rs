@@ -1,14 +1,26 @@
 use crate::{
+    comment::{self, Comment},
     error::{CrackmeError, CrackmeResult},
-    next_parse, BaseCrackme, Language, Platform, Stats,
+    next_parse,
+    solution::{self, Solution},
+    BaseCrackme, Language, Platform, Stats,
 };
 use scraper::{Html, Selector};
-use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OverviewCrackme<'html> {
+    #[cfg_attr(feature = "serde", serde(flatten))]
     base: BaseCrackme<'html>,
-    description: &'html str,
+    description: Cow<'html, str>,
+    // `base` already has `solutions` and `comments`, the counts
+    #[cfg_attr(feature = "serde", serde(rename = "solution_list"))]
+    solutions: Vec<Solution<'html>>,
+    #[cfg_attr(feature = "serde", serde(rename = "comment_list"))]
+    comments: Vec<Comment<'html>>,
 }
 
 impl<'html> fmt::Display for OverviewCrackme<'html> {
@@ -63,61 +75,93 @@ impl<'a> OverviewCrackme<'a> {
         // make sure there (probably) hasn't been a change in the format
         // assert!(info.next().is_none());
 
-        let name = OverviewCrackme::parse_name(&html)?;
+        let name = OverviewCrackme::parse_name(html)?;
 
-        let description = OverviewCrackme::fetch_description(&html)?;
+        let description = OverviewCrackme::fetch_description(html)?;
 
         let stats = Stats {
             quality,
             difficulty,
         };
 
-        let solutions = OverviewCrackme::fetch_solutions(&html);
-        let comments = OverviewCrackme::fetch_comments(&html);
+        let solutions = solution::parse_solutions(html);
+        let comments = comment::parse_comments(html);
 
         // put together our crackme and return it
         let crackme = BaseCrackme {
-            name,
-            author,
+            name: name.into(),
+            author: author.into(),
             language,
-            date,
+            date: date.into(),
             platform,
             stats,
-            id,
-            solutions,
-            comments,
+            id: id.into(),
+            solutions: solution::count_solutions(html) as u64,
+            comments: comment::count_comments(html) as u64,
         };
 
         let overview = OverviewCrackme {
             base: crackme,
-            description,
+            description: description.into(),
+            solutions,
+            comments,
         };
 
         Ok(overview)
     }
 
     pub fn description(&self) -> &str {
-        self.description
+        &self.description
     }
 
     pub fn id(&self) -> &str {
-        self.base.id
+        &self.base.id
     }
 
     pub fn name(&self) -> &str {
-        self.base.name
+        &self.base.name
+    }
+
+    pub fn author(&self) -> &str {
+        &self.base.author
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.base.language
     }
 
-    fn fetch_comments(html: &Html) -> u64 {
-        let selector = Selector::parse("div#comments p").unwrap();
+    pub fn platform(&self) -> &Platform {
+        &self.base.platform
+    }
+
+    pub fn date(&self) -> &str {
+        &self.base.date
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.base.stats
+    }
 
-        html.select(&selector).count() as u64
+    pub fn solutions(&self) -> &[Solution<'a>] {
+        &self.solutions
     }
 
-    fn fetch_solutions(html: &Html) -> u64 {
-        let selector = Selector::parse("div#solutions div.col-9").unwrap();
+    pub fn comments(&self) -> &[Comment<'a>] {
+        &self.comments
+    }
 
-        html.select(&selector).count() as u64
+    /// Converts into a crackme that no longer borrows from the `Html` it was parsed from
+    pub fn into_owned(self) -> OverviewCrackme<'static> {
+        OverviewCrackme {
+            base: self.base.into_owned(),
+            description: Cow::Owned(self.description.into_owned()),
+            solutions: self
+                .solutions
+                .into_iter()
+                .map(Solution::into_owned)
+                .collect(),
+            comments: self.comments.into_iter().map(Comment::into_owned).collect(),
+        }
     }
 
     fn parse_name(html: &Html) -> CrackmeResult<&str> {
@@ -166,24 +210,115 @@ mod tests {
         let crackme = OverviewCrackme::with_full_html(&html, id).unwrap();
 
         assert_eq!(
-            crackme,
-            OverviewCrackme {
-                base: BaseCrackme {
-                    name: "SAFE_01",
-                    author: "oles",
-                    date: "12:44 PM 04/22/2021",
-                    platform: Platform::Windows,
-                    language: Language::VisualBasic,
-                    stats: Stats {
-                        quality: 3.7,
-                        difficulty: 1.0,
-                    },
-                    id,
-                    solutions: 0,
-                    comments: 2,
+            crackme.base,
+            BaseCrackme {
+                name: "SAFE_01".into(),
+                author: "oles".into(),
+                date: "12:44 PM 04/22/2021".into(),
+                platform: Platform::Windows,
+                language: Language::VisualBasic,
+                stats: Stats {
+                    quality: 3.7,
+                    difficulty: 1.0,
                 },
-                description: "easy crackme ..enjoy )",
+                id: id.into(),
+                solutions: 0,
+                comments: 2,
             }
         );
+        assert_eq!(crackme.description(), "easy crackme ..enjoy )");
+        assert!(crackme.solutions().is_empty());
+        assert_eq!(crackme.comments().len(), 2);
+    }
+
+    // the parts of an overview page the parser looks at, with a solution and a comment from a
+    // deleted user
+    const INLINE_PAGE: &str = r#"<html><body>
+        <h3><a href="/user/oles">oles</a>'s SAFE_01</h3>
+        <div class="columns panel-background">
+            <div class="column col-3"><p>Author</p><p>oles</p></div>
+            <div class="column col-3"><p>Language</p><p>(Visual) Basic</p></div>
+            <div class="column col-3"><p>Upload</p><p>12:44 PM 04/22/2021</p></div>
+            <div class="column col-3"><p>Platform</p><p>Windows</p></div>
+            <div class="column col-3"><p>Difficulty</p><p>1.0</p></div>
+            <div class="column col-3"><p>Quality</p><p>3.7</p></div>
+        </div>
+        <div class="columns"><div class="column col-12"><span>easy crackme ..enjoy )</span></div></div>
+        <div id="solutions">
+            <div class="columns">
+                <div class="column col-3">[deleted]<br>1:00 PM 05/01/2021</div>
+                <div class="column col-9">
+                    <p>Gone</p>
+                    <a href="/static/solution/6098001f33c5d458ce0ec8a0.zip">Download</a>
+                </div>
+            </div>
+            <div class="columns">
+                <div class="column col-3"><a href="/user/someone">someone</a><br>3:12 PM 05/09/2021</div>
+                <div class="column col-9">
+                    <p>Patched the jump</p>
+                    <a href="/static/solution/6098001f33c5d458ce0ec8a1.zip">Download</a>
+                </div>
+            </div>
+        </div>
+        <div id="comments">
+            <p>[deleted] on 1:00 PM 04/22/2021: gone</p>
+            <p><a href="/user/someone">someone</a> on 8:28 PM 04/23/2021: works on win10</p>
+        </div>
+    </body></html>"#;
+
+    #[test]
+    fn broken_rows_dont_fail_overview() {
+        let html = Html::parse_document(INLINE_PAGE);
+        let crackme = OverviewCrackme::with_full_html(&html, "60816fca33c5d42f38520831").unwrap();
+
+        assert_eq!(crackme.name(), "SAFE_01");
+        assert_eq!(crackme.description(), "easy crackme ..enjoy )");
+        // the broken rows still count, they just can't be shown
+        assert_eq!(crackme.base.solutions, 2);
+        assert_eq!(crackme.base.comments, 2);
+
+        let comments: Vec<_> = crackme
+            .comments()
+            .iter()
+            .map(|c| (c.author(), c.date(), c.body()))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![("someone", "8:28 PM 04/23/2021", "works on win10")]
+        );
+
+        let solutions: Vec<_> = crackme
+            .solutions()
+            .iter()
+            .map(|s| (s.author(), s.date(), s.description(), s.id()))
+            .collect();
+        assert_eq!(
+            solutions,
+            vec![(
+                "someone",
+                "3:12 PM 05/09/2021",
+                "Patched the jump",
+                "6098001f33c5d458ce0ec8a1"
+            )]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let html = Html::parse_document(INLINE_PAGE);
+        let crackme = OverviewCrackme::with_full_html(&html, "60816fca33c5d42f38520831").unwrap();
+
+        let json = serde_json::to_string(&crackme).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        // the counts and the lists each have a key of their own
+        assert_eq!(value["solutions"], 2);
+        assert_eq!(value["comments"], 2);
+        assert_eq!(value["solution_list"].as_array().map(Vec::len), Some(1));
+        assert_eq!(value["comment_list"].as_array().map(Vec::len), Some(1));
+
+        let parsed: OverviewCrackme<'_> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, crackme);
     }
 }
@@ -0,0 +1,178 @@
+use crate::{
+    error::{CrackmeError, CrackmeResult},
+    Language, Platform,
+};
+
+/// The lowest difficulty/quality rating the site accepts
+pub const MIN_RATING: u8 = 1;
+/// The highest difficulty/quality rating the site accepts
+pub const MAX_RATING: u8 = 6;
+
+/// The criteria for a search on crackmes.one
+#[derive(Debug, PartialEq, Clone)]
+pub struct SearchQuery {
+    name: Option<String>,
+    author: Option<String>,
+    difficulty: (u8, u8),
+    quality: (u8, u8),
+    language: Option<Language>,
+    platform: Option<Platform>,
+    page: u64,
+}
+
+impl Default for SearchQuery {
+    fn default() -> SearchQuery {
+        SearchQuery {
+            name: None,
+            author: None,
+            difficulty: (MIN_RATING, MAX_RATING),
+            quality: (MIN_RATING, MAX_RATING),
+            language: None,
+            platform: None,
+            page: 1,
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn new() -> SearchQuery {
+        SearchQuery::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> SearchQuery {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> SearchQuery {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn difficulty(mut self, min: u8, max: u8) -> CrackmeResult<SearchQuery> {
+        self.difficulty = check_range("difficulty", min, max)?;
+        Ok(self)
+    }
+
+    pub fn quality(mut self, min: u8, max: u8) -> CrackmeResult<SearchQuery> {
+        self.quality = check_range("quality", min, max)?;
+        Ok(self)
+    }
+
+    pub fn language(mut self, language: Language) -> SearchQuery {
+        self.language = Some(language);
+        self
+    }
+
+    pub fn platform(mut self, platform: Platform) -> SearchQuery {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// Which page of the results to get, starting from 1
+    pub fn page(mut self, page: u64) -> SearchQuery {
+        self.page = page.max(1);
+        self
+    }
+
+    /// The form fields to post to the search page, `token` comes from the search page itself
+    pub fn to_form(&self, token: &str) -> Vec<(&'static str, String)> {
+        let mut form = vec![
+            ("name", self.name.clone().unwrap_or_default()),
+            ("author", self.author.clone().unwrap_or_default()),
+            ("difficulty-min", self.difficulty.0.to_string()),
+            ("difficulty-max", self.difficulty.1.to_string()),
+            ("quality-min", self.quality.0.to_string()),
+            ("quality-max", self.quality.1.to_string()),
+            ("token", token.to_string()),
+        ];
+
+        if let Some(l) = &self.language {
+            form.push(("lang", language_value(l).to_string()));
+        }
+
+        if let Some(p) = &self.platform {
+            form.push(("platform", platform_value(p).to_string()));
+        }
+
+        // the first page is what the site gives us without asking
+        if self.page > 1 {
+            form.push(("page", self.page.to_string()));
+        }
+
+        form
+    }
+}
+
+fn check_range(field: &'static str, min: u8, max: u8) -> CrackmeResult<(u8, u8)> {
+    if min < MIN_RATING || max > MAX_RATING || min > max {
+        Err(CrackmeError::InvalidRange(field, min, max))
+    } else {
+        Ok((min, max))
+    }
+}
+
+// the values of the options in the site's search form (these don't always match our Display impl)
+fn language_value(language: &Language) -> &'static str {
+    match language {
+        Language::COrCPlusPlus => "C/C++",
+        Language::Assembler => "Assembler",
+        Language::Java => "Java",
+        Language::VisualBasic => "(Visual) Basic",
+        Language::BorlandDelphi => "Borland Delphi",
+        Language::TurboPascal => "Turbo Pascal",
+        Language::DotNet => ".NET",
+        Language::Other => "Unspecified/other",
+    }
+}
+
+fn platform_value(platform: &Platform) -> &'static str {
+    match platform {
+        Platform::DOS => "DOS",
+        Platform::MacOSX => "Mac OS X",
+        Platform::Multiplatform => "Multiplatform",
+        Platform::UnixLinux => "Unix/linux etc.",
+        Platform::Windows => "Windows",
+        Platform::Windows2000XP => "Windows 2000/XP only",
+        Platform::Windows7 => "Windows 7 Only",
+        Platform::WindowsVista => "Windows Vista Only",
+        Platform::Other => "Unspecified/other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_to_form() {
+        let query = SearchQuery::new()
+            .name("EZwan")
+            .difficulty(1, 3)
+            .unwrap()
+            .language(Language::DotNet)
+            .platform(Platform::UnixLinux);
+
+        assert_eq!(
+            query.to_form("abc"),
+            vec![
+                ("name", "EZwan".to_string()),
+                ("author", String::new()),
+                ("difficulty-min", "1".to_string()),
+                ("difficulty-max", "3".to_string()),
+                ("quality-min", "1".to_string()),
+                ("quality-max", "6".to_string()),
+                ("token", "abc".to_string()),
+                ("lang", ".NET".to_string()),
+                ("platform", "Unix/linux etc.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        assert!(SearchQuery::new().difficulty(0, 6).is_err());
+        assert!(SearchQuery::new().quality(1, 7).is_err());
+        assert!(SearchQuery::new().quality(4, 2).is_err());
+    }
+}
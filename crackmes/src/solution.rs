@@ -0,0 +1,215 @@
+use crate::error::{CrackmeError, CrackmeResult};
+use scraper::{ElementRef, Html, Selector};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
+
+/// A writeup uploaded for a crackme
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Solution<'html> {
+    author: Cow<'html, str>,
+    date: Cow<'html, str>,
+    description: Cow<'html, str>,
+    link: Cow<'html, str>,
+    id: Cow<'html, str>,
+}
+
+impl<'html> fmt::Display for Solution<'html> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Author: {}", self.author)?;
+        writeln!(f, "Upload: {}", self.date)?;
+        writeln!(f, "Download: {}", self.link)?;
+        writeln!(f, "Description: {}", self.description)
+    }
+}
+
+impl<'a> Solution<'a> {
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The path of the solution's zip on the site (i.e. "/static/solution/<id>.zip")
+    pub fn link(&self) -> &str {
+        &self.link
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Converts into a solution that no longer borrows from the `Html` it was parsed from
+    pub fn into_owned(self) -> Solution<'static> {
+        Solution {
+            author: Cow::Owned(self.author.into_owned()),
+            date: Cow::Owned(self.date.into_owned()),
+            description: Cow::Owned(self.description.into_owned()),
+            link: Cow::Owned(self.link.into_owned()),
+            id: Cow::Owned(self.id.into_owned()),
+        }
+    }
+}
+
+/// Parses the solutions listed on a crackme's overview page, leaving out rows that can't be parsed
+/// (i.e. ones from deleted users) so one odd row doesn't hide the crackme
+pub fn parse_solutions(html: &Html) -> Vec<Solution<'_>> {
+    html.select(&solutions_selector())
+        .filter_map(|info| parse_solution(info).ok())
+        .collect()
+}
+
+/// How many solutions the page lists, including any that `parse_solutions` leaves out
+pub fn count_solutions(html: &Html) -> usize {
+    html.select(&solutions_selector()).count()
+}
+
+fn solutions_selector() -> Selector {
+    Selector::parse("div#solutions div.col-9").unwrap()
+}
+
+fn parse_solution(info: ElementRef<'_>) -> CrackmeResult<Solution<'_>> {
+    // Each solution is a row like:
+    // <div class="columns">
+    //     <div class="column col-3">AUTHOR_LINK UPLOAD_DATE</div>
+    //     <div class="column col-9">WRITEUP DOWNLOAD_LINK</div>
+    // </div>
+    let row = info
+        .parent()
+        .and_then(ElementRef::wrap)
+        .ok_or(CrackmeError::NotFound("solution"))?;
+
+    let author = row
+        .select(&Selector::parse("a[href^=\"/user/\"]").unwrap())
+        .next()
+        .and_then(|a| a.text().next())
+        .map(str::trim)
+        .ok_or(CrackmeError::NotFound("solution author"))?;
+
+    let date = row
+        .select(&Selector::parse("div.col-3").unwrap())
+        .next()
+        .and_then(|info| {
+            info.text()
+                .filter(|t| !t.chars().all(char::is_whitespace))
+                .map(|t| t.trim())
+                .nth(1)
+        })
+        .ok_or(CrackmeError::NotFound("solution date"))?;
+
+    let link = row
+        .select(&Selector::parse("a[href$=\".zip\"]").unwrap())
+        .next()
+        .and_then(|a| a.value().attr("href"))
+        .ok_or(CrackmeError::NotFound("solution link"))?;
+
+    let id = link
+        .rsplit('/')
+        .next()
+        .and_then(|file| file.strip_suffix(".zip"))
+        .ok_or(CrackmeError::NotFound("solution ID"))?;
+
+    // everything in the writeup column that isn't part of a link (the download button)
+    let description = info
+        .descendants()
+        .filter(|node| {
+            node.parent()
+                .and_then(ElementRef::wrap)
+                .filter(|parent| parent.value().name() == "a")
+                .is_none()
+        })
+        .filter_map(|node| node.value().as_text())
+        .filter(|t| !t.chars().all(char::is_whitespace))
+        .map(|t| t.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let solution = Solution {
+        author: author.into(),
+        date: date.into(),
+        description: description.into(),
+        link: link.into(),
+        id: id.into(),
+    };
+
+    Ok(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_solution_row() {
+        let html = Html::parse_document(
+            r#"<div id="solutions">
+                <div class="columns panel-background">
+                    <div class="column col-3">
+                        <a href="/user/someone">someone</a><br>
+                        3:12 PM 05/09/2021
+                    </div>
+                    <div class="column col-9">
+                        <p>Patched the jump</p>
+                        <a href="/static/solution/6098001f33c5d458ce0ec8a1.zip">Download</a>
+                    </div>
+                </div>
+            </div>"#,
+        );
+
+        let solutions = parse_solutions(&html);
+
+        assert_eq!(
+            solutions,
+            vec![Solution {
+                author: "someone".into(),
+                date: "3:12 PM 05/09/2021".into(),
+                description: "Patched the jump".into(),
+                link: "/static/solution/6098001f33c5d458ce0ec8a1.zip".into(),
+                id: "6098001f33c5d458ce0ec8a1".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn skip_broken_solution_row() {
+        // the first row's author was deleted, so it has no user link
+        let html = Html::parse_document(
+            r#"<div id="solutions">
+                <div class="columns">
+                    <div class="column col-3">
+                        [deleted]<br>
+                        1:00 PM 05/01/2021
+                    </div>
+                    <div class="column col-9">
+                        <p>Gone</p>
+                        <a href="/static/solution/6098001f33c5d458ce0ec8a0.zip">Download</a>
+                    </div>
+                </div>
+                <div class="columns">
+                    <div class="column col-3">
+                        <a href="/user/someone">someone</a><br>
+                        3:12 PM 05/09/2021
+                    </div>
+                    <div class="column col-9">
+                        <p>Patched the jump</p>
+                        <a href="/static/solution/6098001f33c5d458ce0ec8a1.zip">Download</a>
+                    </div>
+                </div>
+            </div>"#,
+        );
+
+        let solutions = parse_solutions(&html);
+
+        assert_eq!(count_solutions(&html), 2);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].author(), "someone");
+        assert_eq!(solutions[0].id(), "6098001f33c5d458ce0ec8a1");
+    }
+}